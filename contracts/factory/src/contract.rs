@@ -1,20 +1,31 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Reply, Response, StdResult, SubMsg,
-    WasmMsg,
+    instantiate2_address, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Reply,
+    Response, StdError, StdResult, SubMsg, WasmMsg,
 };
 use cw0::parse_reply_instantiate_data;
 use cw2::set_contract_version;
+use packages::asset::AssetInfo;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, PoolInstantiateMsg, QueryMsg};
-use crate::state::{FactoryData, FACTORY_DATA};
+use crate::msg::{
+    ExecuteMsg, InstantiateMsg, MigrateMsg, MigratePoolsTarget, PairKey, PoolParams, PoolType,
+    QueryMsg,
+};
+use crate::state::{
+    pool_kind, FactoryData, PairInfo, PendingPool, FACTORY_DATA, PAIRS, PENDING_POOLS,
+    POOL_CODE_IDS, POOL_INSTANTIATE_REPLY_ID, POOL_SALT_NONCES,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:factory";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Pagination limits for the pool registry
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 /// Handling contract instantiation
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -26,14 +37,16 @@ pub fn instantiate(
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let factory_data = FactoryData {
+        owner: info.sender.to_string(),
         vault_contract: _msg.vault_contract,
-        pool_contract_code_id: _msg.pool_contract_code_id,
-        token0: None,
-        token1: None,
     };
 
     FACTORY_DATA.save(deps.storage, &factory_data)?;
 
+    for pool_code_id in _msg.pool_code_ids {
+        POOL_CODE_IDS.save(deps.storage, pool_code_id.pool_type.as_str(), &pool_code_id)?;
+    }
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender))
@@ -53,91 +66,660 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::CreatePool { token_a, token_b } => {
-            execute::execute_create_pool(_deps, _env, _info, token_a, token_b)
+        ExecuteMsg::CreatePool {
+            token_a,
+            token_b,
+            pool_type,
+            fee_bps,
+            params,
+        } => execute::execute_create_pool(
+            _deps,
+            _env,
+            _info,
+            PairKey {
+                token_a,
+                token_b,
+                pool_type,
+                fee_bps,
+            },
+            params,
+        ),
+        ExecuteMsg::SetPoolCodeId { pool_type, code_id } => {
+            execute::execute_set_pool_code_id(_deps, _env, _info, pool_type, code_id)
         }
+        ExecuteMsg::UpdatePoolFee { pair, fee_bps } => {
+            execute::execute_update_pool_fee(_deps, _env, _info, pair, fee_bps)
+        }
+        ExecuteMsg::MigratePools {
+            new_code_id,
+            target,
+            msg,
+        } => execute::execute_migrate_pools(_deps, _env, _info, new_code_id, target, msg),
     }
 }
 
 pub mod execute {
     use super::*;
+    use crate::msg::PoolCodeId;
+    use cw20_base::msg::InstantiateMarketingInfo;
+    use cw_storage_plus::Bound;
+    use packages::pool_msg::MAX_FEE_BPS;
+    use sha2::{Digest, Sha256};
+
+    /**
+     * sort_tokens: Returns the given token pair in its canonical (sorted) order, which is the
+     * order used as key in `PAIRS` and when registering the pool in the vault.
+     */
+    pub fn sort_tokens(_token_a: AssetInfo, _token_b: AssetInfo) -> (AssetInfo, AssetInfo) {
+        if _token_a.as_str() < _token_b.as_str() {
+            (_token_a, _token_b)
+        } else {
+            (_token_b, _token_a)
+        }
+    }
+
+    /// pair_storage_key: Builds the `PAIRS` key of the pool identified by `_pair`.
+    pub fn pair_storage_key(_pair: PairKey) -> (String, String, String) {
+        let (token0, token1) = sort_tokens(_pair.token_a, _pair.token_b);
+        (
+            token0.to_string(),
+            token1.to_string(),
+            pool_kind(_pair.pool_type, _pair.fee_bps),
+        )
+    }
+
+    /**
+     * pool_salt: Returns the instantiate2 salt of the pool identified by its `PAIRS` key, that is
+     * sha256 over the sorted tokens and the pool kind, each prefixed with its length as a big endian u32.
+     * `_nonce` is the number of pools created under the key before, appended as a big endian u64 when
+     * it isn't zero so the first pool of a key keeps the same salt.
+     */
+    pub fn pool_salt(_pair_key: &(String, String, String), _nonce: u64) -> Binary {
+        let mut hasher = Sha256::new();
+        for part in [&_pair_key.0, &_pair_key.1, &_pair_key.2] {
+            hasher.update((part.len() as u32).to_be_bytes());
+            hasher.update(part.as_bytes());
+        }
+        if _nonce > 0 {
+            hasher.update(_nonce.to_be_bytes());
+        }
+
+        Binary::from(hasher.finalize().to_vec())
+    }
+
+    /**
+     * pool_address: Computes the address instantiate2 assigns to a pool of `_code_id` created by the
+     * factory with `_salt`, the same way the chain does it.
+     */
+    pub fn pool_address(_deps: Deps, _env: &Env, _code_id: u64, _salt: &Binary) -> StdResult<Addr> {
+        let checksum = _deps.querier.query_wasm_code_info(_code_id)?.checksum;
+        let creator = _deps
+            .api
+            .addr_canonicalize(_env.contract.address.as_str())?;
+
+        let canonical_address = instantiate2_address(&checksum, &creator, _salt)
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+        _deps.api.addr_humanize(&canonical_address)
+    }
+
+    fn ensure_owner(_deps: &DepsMut, _info: &MessageInfo) -> Result<(), ContractError> {
+        let factory_data = match FACTORY_DATA.load(_deps.storage) {
+            Ok(data) => data,
+            Err(_) => return Err(ContractError::FactoryDataFetchError {}),
+        };
+
+        if factory_data.owner != _info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        Ok(())
+    }
+
+    /**
+     * token_info: Returns the symbol and decimals of a token. cw20 tokens are queried for their `TokenInfo`,
+     * native denoms for their bank metadata, the decimals being the exponent of its display unit. A denom
+     * without metadata is symbolized after itself, with the 0 decimals of its base unit.
+     */
+    fn token_info(_deps: Deps, _token: &AssetInfo) -> Result<(String, u8), ContractError> {
+        match _token {
+            AssetInfo::Cw20 { contract_addr } => {
+                let token_info: cw20::TokenInfoResponse = _deps
+                    .querier
+                    .query_wasm_smart(contract_addr, &cw20::Cw20QueryMsg::TokenInfo {})
+                    .map_err(|_| ContractError::TokenNotFound {})?;
+
+                Ok((token_info.symbol, token_info.decimals))
+            }
+            AssetInfo::Native { denom } => {
+                let metadata = match _deps.querier.query_denom_metadata(denom) {
+                    Ok(metadata) if !metadata.symbol.is_empty() => metadata,
+                    _ => return Ok((denom.clone(), 0)),
+                };
+
+                let decimals = match metadata
+                    .denom_units
+                    .iter()
+                    .find(|unit| unit.denom == metadata.display)
+                {
+                    Some(unit) => {
+                        u8::try_from(unit.exponent).map_err(|_| ContractError::TokenNotFound {})?
+                    }
+                    None => return Err(ContractError::TokenNotFound {}),
+                };
+
+                Ok((metadata.symbol, decimals))
+            }
+        }
+    }
+
+    /**
+     * lp_token_info: Derives the LP token of a pool from the tokens of its pair. The LP token is
+     * named and symbolized after the token symbols (e.g. `USDC-USDT-LP`) and gets the average of their
+     * decimals, the factory owner becomes its marketing address so the logo can be uploaded later.
+     */
+    fn lp_token_info(
+        _deps: Deps,
+        _token0: &AssetInfo,
+        _token1: &AssetInfo,
+        _pool_type: PoolType,
+        _fee_bps: u16,
+    ) -> Result<(String, u8, InstantiateMarketingInfo), ContractError> {
+        let (symbol0, decimals0) = token_info(_deps, _token0)?;
+        let (symbol1, decimals1) = token_info(_deps, _token1)?;
+        let factory_data = FACTORY_DATA.load(_deps.storage)?;
+
+        let symbol = format!("{}-{}-LP", symbol0, symbol1);
+        let decimals = ((decimals0 as u16 + decimals1 as u16) / 2) as u8;
+        let marketing = InstantiateMarketingInfo {
+            project: None,
+            description: Some(format!(
+                "Liquidity provider token of the {}-{} {} pool with a {} bps swap fee",
+                symbol0,
+                symbol1,
+                _pool_type.as_str(),
+                _fee_bps
+            )),
+            marketing: Some(factory_data.owner),
+            logo: None,
+        };
+
+        Ok((symbol, decimals, marketing))
+    }
+
+    /**
+     * pool_instantiate_msg: Builds the instantiate message of a pool of `_pool_type` for the sorted token pair,
+     * checking that the given parameters belong to that pool type.
+     */
+    fn pool_instantiate_msg(
+        _deps: Deps,
+        _token0: &AssetInfo,
+        _token1: &AssetInfo,
+        _pool_type: PoolType,
+        _fee_bps: u16,
+        _params: Option<PoolParams>,
+    ) -> Result<Binary, ContractError> {
+        let msg = match (_pool_type, _params) {
+            (PoolType::ConstantProduct, None) => {
+                let (symbol, decimals, marketing) =
+                    lp_token_info(_deps, _token0, _token1, _pool_type, _fee_bps)?;
+
+                to_binary(&uniswapv2_pool::msg::InstantiateMsg {
+                    name: symbol.clone(),
+                    symbol,
+                    decimals,
+                    fee_bps: _fee_bps,
+                    marketing: Some(marketing),
+                })?
+            }
+            (
+                PoolType::ConcentratedLiquidity,
+                Some(PoolParams::ConcentratedLiquidity { tick_spacing }),
+            ) => to_binary(&uniswapv3_pool::msg::InstantiateMsg {
+                tick_spacing,
+                fee_bps: _fee_bps,
+            })?,
+            (PoolType::StableSwap, Some(PoolParams::StableSwap { amp })) => {
+                let (symbol, decimals, marketing) =
+                    lp_token_info(_deps, _token0, _token1, _pool_type, _fee_bps)?;
+
+                to_binary(&packages::pool_msg::StablePoolInstantiateMsg {
+                    name: symbol.clone(),
+                    symbol,
+                    decimals,
+                    fee_bps: _fee_bps,
+                    marketing: Some(marketing),
+                    amp,
+                })?
+            }
+            (pool_type, _) => {
+                return Err(ContractError::InvalidPoolParams {
+                    pool_type: pool_type.as_str().to_string(),
+                })
+            }
+        };
+
+        Ok(msg)
+    }
 
     pub fn execute_create_pool(
         _deps: DepsMut,
         _env: Env,
         _info: MessageInfo,
-        _token_a: String,
-        _token_b: String,
+        _pair: PairKey,
+        _params: Option<PoolParams>,
     ) -> Result<Response, ContractError> {
-        if _token_a == _token_b {
+        if _pair.token_a == _pair.token_b {
             return Err(ContractError::IdenticalAddresses {});
         }
 
-        let (token0, token1) = (_token_a, _token_b);
-
-        if token0 == String::from("") && token1 == String::from("") {
+        if _pair.token_a.as_str().is_empty() || _pair.token_b.as_str().is_empty() {
             return Err(ContractError::EmptyAddresses {});
         }
 
-        let fetch_factory_data = FACTORY_DATA.load(_deps.storage);
-        match fetch_factory_data {
-            Ok(data) => {
-                let update_factory = FACTORY_DATA.update(
-                    _deps.storage,
-                    |mut factory_data| -> StdResult<FactoryData> {
-                        factory_data.token0 = Some(token0);
-                        factory_data.token1 = Some(token1);
-                        Ok(factory_data)
-                    },
-                );
-
-                match update_factory {
-                    Ok(_) => {
-                        let pool_instantiate_tx = WasmMsg::Instantiate {
-                            admin: None,
-                            code_id: data.pool_contract_code_id,
-                            msg: to_binary(&uniswapv2_pool::msg::InstantiateMsg {
-                                name: String::from("pool_lp"),
-                                symbol: String::from("POOL_LP"),
-                                decimals: 18,
-                            })?,
-                            funds: vec![],
-                            label: "pool_contract".to_string(),
-                        };
-
-                        const POOL_INSTANTIATE_TX_ID: u64 = 1u64;
-
-                        let submessage =
-                            SubMsg::reply_on_success(pool_instantiate_tx, POOL_INSTANTIATE_TX_ID);
-
-                        Ok(Response::new()
-                            .add_submessage(submessage)
-                            .add_attribute("function", "execute_create_pool"))
-                    }
-                    Err(_) => return Err(ContractError::FactoryDataUpdateError {}),
-                }
+        _pair.token_a.check(_deps.api)?;
+        _pair.token_b.check(_deps.api)?;
+
+        if _pair.fee_bps > MAX_FEE_BPS {
+            return Err(ContractError::InvalidFee {
+                fee_bps: _pair.fee_bps,
+            });
+        }
+
+        let (_pool_type, _fee_bps) = (_pair.pool_type, _pair.fee_bps);
+        let (token0, token1) = sort_tokens(_pair.token_a.clone(), _pair.token_b.clone());
+        let pair_key = pair_storage_key(_pair);
+
+        // A pair can only be created once per pool type and fee tier, including while its creation is still pending
+        let pending_duplicate = PENDING_POOLS
+            .range(_deps.storage, None, None, Order::Ascending)
+            .filter_map(|item| item.ok())
+            .any(|(_, pending)| {
+                pending.token0 == token0
+                    && pending.token1 == token1
+                    && pending.pool_type == _pool_type
+                    && pending.fee_bps == _fee_bps
+            });
+
+        if pending_duplicate || PAIRS.has(_deps.storage, pair_key.clone()) {
+            return Err(ContractError::PairAlreadyExists {
+                token0: token0.to_string(),
+                token1: token1.to_string(),
+            });
+        }
+
+        let pool_code_id = match POOL_CODE_IDS.may_load(_deps.storage, _pool_type.as_str())? {
+            Some(pool_code_id) => pool_code_id.code_id,
+            None => {
+                return Err(ContractError::PoolCodeIdNotFound {
+                    pool_type: _pool_type.as_str().to_string(),
+                })
             }
-            Err(_) => return Err(ContractError::FactoryDataFetchError {}),
+        };
+
+        let instantiate_msg = pool_instantiate_msg(
+            _deps.as_ref(),
+            &token0,
+            &token1,
+            _pool_type,
+            _fee_bps,
+            _params,
+        )?;
+
+        // The pool address only depends on the pair and the pools created under its key before, so it is
+        // known before the pool exists
+        let nonce = POOL_SALT_NONCES
+            .may_load(_deps.storage, pair_key.clone())?
+            .unwrap_or_default();
+        POOL_SALT_NONCES.save(_deps.storage, pair_key.clone(), &(nonce + 1))?;
+        let salt = pool_salt(&pair_key, nonce);
+        let pool_address = pool_address(_deps.as_ref(), &_env, pool_code_id, &salt)?;
+
+        // Every creation gets its own reply id so its tokens are kept apart from other creations
+        let reply_id = POOL_INSTANTIATE_REPLY_ID
+            .may_load(_deps.storage)?
+            .unwrap_or_default()
+            + 1;
+        POOL_INSTANTIATE_REPLY_ID.save(_deps.storage, &reply_id)?;
+
+        PENDING_POOLS.save(
+            _deps.storage,
+            reply_id,
+            &PendingPool {
+                token0: token0.clone(),
+                token1: token1.clone(),
+                pool_type: _pool_type,
+                fee_bps: _fee_bps,
+                salt: salt.clone(),
+            },
+        )?;
+
+        // The factory stays admin of its pools so they can be migrated with `MigratePools`
+        let pool_instantiate_tx = WasmMsg::Instantiate2 {
+            admin: Some(_env.contract.address.to_string()),
+            code_id: pool_code_id,
+            label: format!("{}_pool_contract", _pool_type.as_str()),
+            msg: instantiate_msg,
+            funds: vec![],
+            salt,
+        };
+
+        let submessage = SubMsg::reply_on_success(pool_instantiate_tx, reply_id);
+
+        Ok(Response::new()
+            .add_submessage(submessage)
+            .add_attribute("function", "execute_create_pool")
+            .add_attribute("token0", token0.to_string())
+            .add_attribute("token1", token1.to_string())
+            .add_attribute("pool_type", _pool_type.as_str())
+            .add_attribute("fee_bps", _fee_bps.to_string())
+            .add_attribute("pool_address", pool_address))
+    }
+
+    pub fn execute_set_pool_code_id(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _pool_type: PoolType,
+        _code_id: u64,
+    ) -> Result<Response, ContractError> {
+        ensure_owner(&_deps, &_info)?;
+
+        POOL_CODE_IDS.save(
+            _deps.storage,
+            _pool_type.as_str(),
+            &PoolCodeId {
+                pool_type: _pool_type,
+                code_id: _code_id,
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_set_pool_code_id")
+            .add_attribute("pool_type", _pool_type.as_str())
+            .add_attribute("code_id", _code_id.to_string()))
+    }
+
+    /**
+     * execute_update_pool_fee: Moves a pool to a new fee tier. The registry entry is re-keyed under
+     * the new fee so `PAIRS` always reflects the fee the pool is charging, and the new fee is
+     * forwarded to the pool, which only accepts fee updates from the factory.
+     */
+    pub fn execute_update_pool_fee(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _pair: PairKey,
+        _fee_bps: u16,
+    ) -> Result<Response, ContractError> {
+        ensure_owner(&_deps, &_info)?;
+
+        if _fee_bps > MAX_FEE_BPS {
+            return Err(ContractError::InvalidFee { fee_bps: _fee_bps });
         }
+
+        let pool_type = _pair.pool_type;
+        let pair_key = pair_storage_key(_pair);
+        let (token0, token1) = (pair_key.0.clone(), pair_key.1.clone());
+
+        let mut pair_info = match PAIRS.may_load(_deps.storage, pair_key.clone())? {
+            Some(pair_info) => pair_info,
+            None => return Err(ContractError::PairNotFound {}),
+        };
+
+        let new_pair_key = (
+            token0.clone(),
+            token1.clone(),
+            pool_kind(pool_type, _fee_bps),
+        );
+        if PAIRS.has(_deps.storage, new_pair_key.clone()) {
+            return Err(ContractError::PairAlreadyExists { token0, token1 });
+        }
+
+        PAIRS.remove(_deps.storage, pair_key);
+        pair_info.fee_bps = _fee_bps;
+        PAIRS.save(_deps.storage, new_pair_key, &pair_info)?;
+
+        let update_fee_tx = WasmMsg::Execute {
+            contract_addr: pair_info.pool_address.clone(),
+            msg: to_binary(&packages::pool_msg::PoolExecuteMsg::UpdateFee { fee_bps: _fee_bps })?,
+            funds: vec![],
+        };
+
+        Ok(Response::new()
+            .add_message(update_fee_tx)
+            .add_attribute("function", "execute_update_pool_fee")
+            .add_attribute("pool_address", pair_info.pool_address)
+            .add_attribute("fee_bps", _fee_bps.to_string()))
+    }
+
+    /**
+     * execute_migrate_pools: Sends a `WasmMsg::Migrate` to every targeted pool. With
+     * `MigratePoolsTarget::All` a page of the registry is scanned per call, so registries
+     * of any size can be migrated without running out of gas. The pair scanned last is set
+     * as the data of the response, the `start_after` of the next page.
+     */
+    pub fn execute_migrate_pools(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _new_code_id: u64,
+        _target: MigratePoolsTarget,
+        _msg: Binary,
+    ) -> Result<Response, ContractError> {
+        ensure_owner(&_deps, &_info)?;
+
+        let (pool_addresses, last_scanned) = match _target {
+            MigratePoolsTarget::Pools { pool_addresses } => (pool_addresses, None),
+            MigratePoolsTarget::All {
+                pool_type,
+                start_after,
+                limit,
+            } => {
+                let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+                let start = start_after.map(|pair| Bound::exclusive(pair_storage_key(pair)));
+
+                // The limit bounds the scanned pairs, pairs of other pool types count towards it
+                let scanned = PAIRS
+                    .range(_deps.storage, start, None, Order::Ascending)
+                    .take(limit)
+                    .map(|item| item.map(|(_, pair_info)| pair_info))
+                    .collect::<StdResult<Vec<_>>>()?;
+
+                let last_scanned = scanned.last().map(|pair_info| PairKey {
+                    token_a: pair_info.token0.clone(),
+                    token_b: pair_info.token1.clone(),
+                    pool_type: pair_info.pool_type,
+                    fee_bps: pair_info.fee_bps,
+                });
+                let pool_addresses = scanned
+                    .into_iter()
+                    .filter(|pair_info| pair_info.pool_type == pool_type)
+                    .map(|pair_info| pair_info.pool_address)
+                    .collect();
+
+                (pool_addresses, last_scanned)
+            }
+        };
+
+        let migrate_messages: Vec<WasmMsg> = pool_addresses
+            .iter()
+            .map(|pool_address| WasmMsg::Migrate {
+                contract_addr: pool_address.clone(),
+                new_code_id: _new_code_id,
+                msg: _msg.clone(),
+            })
+            .collect();
+
+        let mut response = Response::new();
+        if let Some(last_scanned) = last_scanned {
+            response = response.set_data(to_binary(&last_scanned)?);
+        }
+
+        Ok(response
+            .add_messages(migrate_messages)
+            .add_attribute("function", "execute_migrate_pools")
+            .add_attribute("new_code_id", _new_code_id.to_string())
+            .add_attribute("migrated_pools", pool_addresses.join(",")))
     }
 }
 
 /// Handling contract query
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(_deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {}
+    match msg {
+        QueryMsg::Pairs { start_after, limit } => {
+            to_binary(&query::query_pairs(_deps, start_after, limit)?)
+        }
+        QueryMsg::Pair {
+            token_a,
+            token_b,
+            pool_type,
+            fee_bps,
+        } => to_binary(&query::query_pair(
+            _deps,
+            PairKey {
+                token_a,
+                token_b,
+                pool_type,
+                fee_bps,
+            },
+        )?),
+        QueryMsg::Config {} => to_binary(&query::query_config(_deps)?),
+        QueryMsg::PredictPoolAddress {
+            token_a,
+            token_b,
+            pool_type,
+            fee_bps,
+        } => to_binary(&query::query_predict_pool_address(
+            _deps,
+            _env,
+            PairKey {
+                token_a,
+                token_b,
+                pool_type,
+                fee_bps,
+            },
+        )?),
+    }
+}
+
+pub mod query {
+    use super::*;
+    use crate::msg::{ConfigResponse, PairResponse, PairsResponse, PredictPoolAddressResponse};
+    use cw_storage_plus::Bound;
+
+    pub fn query_pairs(
+        _deps: Deps,
+        _start_after: Option<PairKey>,
+        _limit: Option<u32>,
+    ) -> StdResult<PairsResponse> {
+        let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = _start_after.map(|pair| Bound::exclusive(execute::pair_storage_key(pair)));
+
+        let pairs = PAIRS
+            .range(_deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(_, pair_info)| PairResponse {
+                    token0: pair_info.token0,
+                    token1: pair_info.token1,
+                    pool_type: pair_info.pool_type,
+                    fee_bps: pair_info.fee_bps,
+                    pool_address: pair_info.pool_address,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(PairsResponse { pairs })
+    }
+
+    pub fn query_pair(_deps: Deps, _pair: PairKey) -> StdResult<PairResponse> {
+        match PAIRS.may_load(_deps.storage, execute::pair_storage_key(_pair))? {
+            Some(pair_info) => Ok(PairResponse {
+                token0: pair_info.token0,
+                token1: pair_info.token1,
+                pool_type: pair_info.pool_type,
+                fee_bps: pair_info.fee_bps,
+                pool_address: pair_info.pool_address,
+            }),
+            None => Err(StdError::GenericErr {
+                msg: "Pair does not exist".to_string(),
+            }),
+        }
+    }
+
+    pub fn query_config(_deps: Deps) -> StdResult<ConfigResponse> {
+        let factory_data = FACTORY_DATA.load(_deps.storage)?;
+
+        let pool_code_ids = POOL_CODE_IDS
+            .range(_deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, pool_code_id)| pool_code_id))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ConfigResponse {
+            owner: factory_data.owner,
+            vault_contract: factory_data.vault_contract,
+            pool_code_ids,
+        })
+    }
+
+    /**
+     * query_predict_pool_address: Returns the address of the pool registered for the pair, or the address
+     * its next pool is created at with the current code ID of its pool type.
+     */
+    pub fn query_predict_pool_address(
+        _deps: Deps,
+        _env: Env,
+        _pair: PairKey,
+    ) -> StdResult<PredictPoolAddressResponse> {
+        let pool_type = _pair.pool_type;
+        let pair_key = execute::pair_storage_key(_pair);
+
+        if let Some(pair_info) = PAIRS.may_load(_deps.storage, pair_key.clone())? {
+            return Ok(PredictPoolAddressResponse {
+                pool_address: pair_info.pool_address,
+                salt: pair_info.salt,
+            });
+        }
+
+        let pool_code_id = match POOL_CODE_IDS.may_load(_deps.storage, pool_type.as_str())? {
+            Some(pool_code_id) => pool_code_id.code_id,
+            None => {
+                return Err(StdError::GenericErr {
+                    msg: format!("No code id set for {} pools", pool_type.as_str()),
+                })
+            }
+        };
+
+        let nonce = POOL_SALT_NONCES
+            .may_load(_deps.storage, pair_key.clone())?
+            .unwrap_or_default();
+        let salt = execute::pool_salt(&pair_key, nonce);
+        let pool_address = execute::pool_address(_deps, &_env, pool_code_id, &salt)?;
+
+        Ok(PredictPoolAddressResponse {
+            pool_address: pool_address.to_string(),
+            salt,
+        })
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(_deps: DepsMut, _env: Env, _msg: Reply) -> Result<Response, ContractError> {
-    const POOL_INSTANTIATE_TX_ID: u64 = 1u64;
-    match _msg.id {
-        POOL_INSTANTIATE_TX_ID => handle_pool_instantiate(_deps, _msg),
-        _id => return Err(ContractError::ReplyIdError {}),
+    match PENDING_POOLS.may_load(_deps.storage, _msg.id)? {
+        Some(pending_pool) => handle_pool_instantiate(_deps, _msg, pending_pool),
+        None => Err(ContractError::ReplyIdError {}),
     }
 }
 
-pub fn handle_pool_instantiate(_deps: DepsMut, _msg: Reply) -> Result<Response, ContractError> {
+pub fn handle_pool_instantiate(
+    _deps: DepsMut,
+    _msg: Reply,
+    _pending_pool: PendingPool,
+) -> Result<Response, ContractError> {
+    PENDING_POOLS.remove(_deps.storage, _msg.id);
+
     let res = parse_reply_instantiate_data(_msg);
 
     match res {
@@ -146,16 +728,42 @@ pub fn handle_pool_instantiate(_deps: DepsMut, _msg: Reply) -> Result<Response,
 
             match fetch_factory_data {
                 Ok(factory_data) => {
+                    let pair_key = (
+                        _pending_pool.token0.to_string(),
+                        _pending_pool.token1.to_string(),
+                        pool_kind(_pending_pool.pool_type, _pending_pool.fee_bps),
+                    );
+
+                    if PAIRS.has(_deps.storage, pair_key.clone()) {
+                        return Err(ContractError::PairAlreadyExists {
+                            token0: _pending_pool.token0.to_string(),
+                            token1: _pending_pool.token1.to_string(),
+                        });
+                    }
+
+                    PAIRS.save(
+                        _deps.storage,
+                        pair_key,
+                        &PairInfo {
+                            token0: _pending_pool.token0.clone(),
+                            token1: _pending_pool.token1.clone(),
+                            pool_type: _pending_pool.pool_type,
+                            fee_bps: _pending_pool.fee_bps,
+                            pool_address: data.contract_address.clone(),
+                            salt: _pending_pool.salt,
+                        },
+                    )?;
+
+                    // The vault prices every pool it holds as a constant product pool, the other pool
+                    // types are only kept in the registry until the vault supports them
+                    if _pending_pool.pool_type != PoolType::ConstantProduct {
+                        return Ok(Response::new());
+                    }
+
                     let register_pool_params = packages::vault_msg::RegisterPoolParams {
                         pool_address: data.contract_address,
-                        token0: match factory_data.token0 {
-                            Some(data) => data,
-                            None => return Err(ContractError::TokenNotFound {})
-                        },
-                        token1: match factory_data.token1 {
-                            Some(data) => data,
-                            None => return Err(ContractError::TokenNotFound {})
-                        },
+                        token0: _pending_pool.token0,
+                        token1: _pending_pool.token1,
                     };
 
                     let vault_execute_tx = WasmMsg::Execute {
@@ -168,9 +776,9 @@ pub fn handle_pool_instantiate(_deps: DepsMut, _msg: Reply) -> Result<Response,
 
                     Ok(Response::new().add_message(vault_execute_tx))
                 }
-                Err(_) => return Err(ContractError::FactoryDataFetchError {}),
+                Err(_) => Err(ContractError::FactoryDataFetchError {}),
             }
         }
-        Err(_) => return Err(ContractError::ReplyDataError {}),
+        Err(_) => Err(ContractError::ReplyDataError {}),
     }
 }
@@ -30,6 +30,24 @@ pub enum ContractError {
     #[error("Unable to find token")]
     TokenNotFound {},
 
+    #[error("Pair {token0}-{token1} already exists")]
+    PairAlreadyExists { token0: String, token1: String },
+
+    #[error("No code id registered for pool type {pool_type}")]
+    PoolCodeIdNotFound { pool_type: String },
+
+    #[error("Invalid instantiate params for pool type {pool_type}")]
+    InvalidPoolParams { pool_type: String },
+
+    #[error(
+        "Invalid fee {fee_bps} bps, the maximum is {} bps",
+        packages::pool_msg::MAX_FEE_BPS
+    )]
+    InvalidFee { fee_bps: u16 },
+
+    #[error("Pair does not exist")]
+    PairNotFound {},
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
 
@@ -1,10 +1,12 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Binary;
+use packages::asset::AssetInfo;
 
 /// Message type for `instantiate` entry_point
 #[cw_serde]
 pub struct InstantiateMsg {
-    // A field to specify the code ID of the pool contract
-    pub pool_contract_code_id: u64,
+    // Code IDs of the pool contracts, one per supported pool type
+    pub pool_code_ids: Vec<PoolCodeId>,
 
     // A field to specify the address of the vault contract
     pub vault_contract: String
@@ -20,10 +22,98 @@ pub struct PoolInstantiateMsg {
     pub decimals: u8,
 }
 
+/**
+ * PoolType: The pool families the factory is able to create.
+ *
+ * - `ConstantProduct`: x * y = k pools backed by the `uniswapv2-pool` contract.
+ * - `ConcentratedLiquidity`: tick based pools backed by the `uniswapv3-pool` contract.
+ * - `StableSwap`: curve style pools for pegged assets.
+ */
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum PoolType {
+    ConstantProduct,
+    ConcentratedLiquidity,
+    StableSwap,
+}
+
+impl PoolType {
+    /// Storage key of the pool type, matches its serialized name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PoolType::ConstantProduct => "constant_product",
+            PoolType::ConcentratedLiquidity => "concentrated_liquidity",
+            PoolType::StableSwap => "stable_swap",
+        }
+    }
+}
+
+/// Instantiate parameters specific to a pool type
+#[cw_serde]
+pub enum PoolParams {
+    ConcentratedLiquidity { tick_spacing: u32 },
+    StableSwap { amp: u64 },
+}
+
+#[cw_serde]
+pub struct PoolCodeId {
+    pub pool_type: PoolType,
+    pub code_id: u64,
+}
+
 /// Message type for `execute` entry_point
 #[cw_serde]
 pub enum ExecuteMsg {
-    CreatePool { token_a: String, token_b: String }
+    /**
+     * CreatePool: Instantiates a new pool of `pool_type` for the token pair and registers it in the vault.
+     * Only constant product pools are registered in the vault for now, the other pool types are kept in the
+     * factory registry until the vault supports them. Each token can be a cw20 token or a native denom.
+     * `fee_bps` is the swap fee of the pool, every fee tier of a pair gets its own pool.
+     * `params` carries the instantiate parameters of the pool type, constant product pools take none.
+     */
+    CreatePool {
+        token_a: AssetInfo,
+        token_b: AssetInfo,
+        pool_type: PoolType,
+        fee_bps: u16,
+        params: Option<PoolParams>,
+    },
+
+    /// SetPoolCodeId: Sets the code ID used for new pools of `pool_type`, only callable by the owner.
+    SetPoolCodeId { pool_type: PoolType, code_id: u64 },
+
+    /**
+     * UpdatePoolFee: Moves the pool identified by `pair` to a new fee tier, only callable by the owner.
+     * The factory is the governance of the pools it creates, so it forwards the new fee to the pool.
+     */
+    UpdatePoolFee { pair: PairKey, fee_bps: u16 },
+
+    /**
+     * MigratePools: Migrates pools created by the factory to `new_code_id`, only callable by the owner.
+     * The factory is the admin of every pool it creates. `msg` is passed to the migrate entry point of each pool.
+     * Large registries are migrated in batches by paginating with `MigratePoolsTarget::All`.
+     */
+    MigratePools {
+        new_code_id: u64,
+        target: MigratePoolsTarget,
+        msg: Binary,
+    },
+}
+
+#[cw_serde]
+pub enum MigratePoolsTarget {
+    /// The given pool addresses
+    Pools { pool_addresses: Vec<String> },
+
+    /**
+     * The pools of `pool_type` in a page of `limit` pairs, in the order of the `Pairs` query. The last
+     * pair of the page is returned as the data of the response, the `start_after` of the next page.
+     */
+    All {
+        pool_type: PoolType,
+        start_after: Option<PairKey>,
+        limit: Option<u32>,
+    },
 }
 
 /// Message type for `migrate` entry_point
@@ -34,15 +124,72 @@ pub enum MigrateMsg {}
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-    // This example query variant indicates that any client can query the contract
-    // using `YourQuery` and it will return `YourQueryResponse`
-    // This `returns` information will be included in contract's schema
-    // which is used for client code generation.
-    //
-    // #[returns(YourQueryResponse)]
-    // YourQuery {},
-}
-
-// We define a custom struct for each query response
-// #[cw_serde]
-// pub struct YourQueryResponse {}
+    /// Lists the pools created by the factory, ordered by their canonical token pair, pool type and fee tier
+    #[returns(PairsResponse)]
+    Pairs {
+        start_after: Option<PairKey>,
+        limit: Option<u32>,
+    },
+
+    /// Looks up the pool of a token pair, the tokens can be given in any order
+    #[returns(PairResponse)]
+    Pair {
+        token_a: AssetInfo,
+        token_b: AssetInfo,
+        pool_type: PoolType,
+        fee_bps: u16,
+    },
+
+    #[returns(ConfigResponse)]
+    Config {},
+
+    /**
+     * PredictPoolAddress: Returns the instantiate2 address of the pool of a token pair, the tokens can be given in any order.
+     * The salt is sha256 over the sorted token contract addresses or denoms and the pool kind (`<pool_type>_<fee_bps padded to 5 digits>`),
+     * each prefixed with its byte length as a big endian u32, so clients can also compute it offline. When pools were created
+     * in the tier before, their number follows as a big endian u64.
+     * Pools moved to another fee tier with `UpdatePoolFee` keep their address and salt, they are returned for the new tier.
+     */
+    #[returns(PredictPoolAddressResponse)]
+    PredictPoolAddress {
+        token_a: AssetInfo,
+        token_b: AssetInfo,
+        pool_type: PoolType,
+        fee_bps: u16,
+    },
+}
+
+#[cw_serde]
+pub struct PairKey {
+    pub token_a: AssetInfo,
+    pub token_b: AssetInfo,
+    pub pool_type: PoolType,
+    pub fee_bps: u16,
+}
+
+#[cw_serde]
+pub struct PairResponse {
+    pub token0: AssetInfo,
+    pub token1: AssetInfo,
+    pub pool_type: PoolType,
+    pub fee_bps: u16,
+    pub pool_address: String,
+}
+
+#[cw_serde]
+pub struct PairsResponse {
+    pub pairs: Vec<PairResponse>,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub owner: String,
+    pub vault_contract: String,
+    pub pool_code_ids: Vec<PoolCodeId>,
+}
+
+#[cw_serde]
+pub struct PredictPoolAddressResponse {
+    pub pool_address: String,
+    pub salt: Binary,
+}
@@ -1,12 +1,72 @@
-use cw_storage_plus::Item;
-use serde::{Serialize, Deserialize};
+use cosmwasm_std::Binary;
+use cw_storage_plus::{Item, Map};
+use packages::asset::AssetInfo;
+use serde::{Deserialize, Serialize};
+
+use crate::msg::{PoolCodeId, PoolType};
 
 #[derive(Serialize, Deserialize)]
 pub struct FactoryData {
+    pub owner: String,
     pub vault_contract: String,
-    pub pool_contract_code_id: u64,
-    pub token0: Option<String>,
-    pub token1: Option<String>
 }
 
-pub const FACTORY_DATA: Item<FactoryData> = Item::new("pool_contract_code_id");
\ No newline at end of file
+pub const FACTORY_DATA: Item<FactoryData> = Item::new("pool_contract_code_id");
+
+/**
+ * POOL_CODE_IDS: Owner managed table of the code IDs used to instantiate pools.
+ * The key is the storage key of the `PoolType` (see `PoolType::as_str`).
+ */
+pub const POOL_CODE_IDS: Map<&str, PoolCodeId> = Map::new("pool_code_ids");
+
+#[derive(Serialize, Deserialize)]
+pub struct PairInfo {
+    pub token0: AssetInfo,
+    pub token1: AssetInfo,
+    pub pool_type: PoolType,
+    pub fee_bps: u16,
+    pub pool_address: String,
+    /// instantiate2 salt the pool was created with, kept when the pool moves to another fee tier
+    pub salt: Binary,
+}
+
+/**
+ * PAIRS: Registry of every pool created by the factory.
+ *
+ * The key is the canonical (lexicographically sorted) pair of token contract addresses or denoms
+ * `(token0, token1)` (see `AssetInfo::as_str`) followed by
+ * the pool kind (see `pool_kind`), so the same pair can only be created once per pool type and
+ * fee tier regardless of the order the tokens are given in.
+ */
+pub const PAIRS: Map<(String, String, String), PairInfo> = Map::new("pairs");
+
+/**
+ * POOL_SALT_NONCES: Number of pools created under each `PAIRS` key, including the pools moved to another
+ * fee tier since. It is part of the instantiate2 salt, so a key freed by `UpdatePoolFee` gets a new address.
+ */
+pub const POOL_SALT_NONCES: Map<(String, String, String), u64> = Map::new("pool_salt_nonces");
+
+/// Last component of the `PAIRS` key, the fee is zero padded so fee tiers are listed in order
+pub fn pool_kind(pool_type: PoolType, fee_bps: u16) -> String {
+    format!("{}_{:05}", pool_type.as_str(), fee_bps)
+}
+
+/// Token pair of a pool whose instantiation is still waiting for its reply.
+#[derive(Serialize, Deserialize)]
+pub struct PendingPool {
+    pub token0: AssetInfo,
+    pub token1: AssetInfo,
+    pub pool_type: PoolType,
+    pub fee_bps: u16,
+    pub salt: Binary,
+}
+
+/**
+ * PENDING_POOLS: In-flight pool creations keyed by the reply id of their instantiate
+ * submessage. Every `CreatePool` gets its own reply id, so replies can never pick up the
+ * tokens of another creation handled in the same block.
+ */
+pub const PENDING_POOLS: Map<u64, PendingPool> = Map::new("pending_pools");
+
+// Last reply id handed out to a pool instantiate submessage.
+pub const POOL_INSTANTIATE_REPLY_ID: Item<u64> = Item::new("pool_instantiate_reply_id");
@@ -0,0 +1,144 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Api, BankMsg, Coin, CosmosMsg, MessageInfo, QuerierWrapper, StdError, StdResult,
+    Uint128, WasmMsg,
+};
+use std::fmt;
+
+/**
+ * AssetInfo: Identifies a token a pool can hold.
+ *
+ * - `Cw20`: a cw20 token contract, moved with `Transfer` / `TransferFrom`.
+ * - `Native`: a bank denom (including IBC and tokenfactory denoms), moved with `BankMsg::Send`
+ *   and deposited by attaching it as `funds`.
+ */
+#[cw_serde]
+#[derive(Eq, PartialOrd, Ord, Hash)]
+pub enum AssetInfo {
+    Cw20 { contract_addr: String },
+    Native { denom: String },
+}
+
+impl AssetInfo {
+    pub fn is_native(&self) -> bool {
+        matches!(self, AssetInfo::Native { .. })
+    }
+
+    /// Contract address or denom of the asset, used as its key in storage and events
+    pub fn as_str(&self) -> &str {
+        match self {
+            AssetInfo::Cw20 { contract_addr } => contract_addr,
+            AssetInfo::Native { denom } => denom,
+        }
+    }
+
+    /// Checks that the asset points to a valid cw20 address or a non empty denom
+    pub fn check(&self, api: &dyn Api) -> StdResult<()> {
+        match self {
+            AssetInfo::Cw20 { contract_addr } => api.addr_validate(contract_addr).map(|_| ()),
+            AssetInfo::Native { denom } if denom.is_empty() => {
+                Err(StdError::generic_err("Empty denom"))
+            }
+            AssetInfo::Native { .. } => Ok(()),
+        }
+    }
+
+    /// Message sending `amount` of the asset held by the contract to `recipient`
+    pub fn transfer_msg(&self, recipient: &str, amount: Uint128) -> StdResult<CosmosMsg> {
+        match self {
+            AssetInfo::Cw20 { contract_addr } => Ok(WasmMsg::Execute {
+                contract_addr: contract_addr.clone(),
+                msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            }
+            .into()),
+            AssetInfo::Native { denom } => Ok(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin {
+                    denom: denom.clone(),
+                    amount,
+                }],
+            }
+            .into()),
+        }
+    }
+
+    /**
+     * transfer_from_msg: Message pulling `amount` of a cw20 asset from `owner` to `recipient` using the
+     * allowance of the contract. Native assets can't be pulled, they must be attached as funds instead.
+     */
+    pub fn transfer_from_msg(
+        &self,
+        owner: &str,
+        recipient: &str,
+        amount: Uint128,
+    ) -> StdResult<CosmosMsg> {
+        match self {
+            AssetInfo::Cw20 { contract_addr } => Ok(WasmMsg::Execute {
+                contract_addr: contract_addr.clone(),
+                msg: to_binary(&cw20::Cw20ExecuteMsg::TransferFrom {
+                    owner: owner.to_string(),
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            }
+            .into()),
+            AssetInfo::Native { denom } => Err(StdError::generic_err(format!(
+                "Native {} must be sent as funds",
+                denom
+            ))),
+        }
+    }
+
+    /// Balance of the asset held by `address`
+    pub fn query_balance(&self, querier: &QuerierWrapper, address: &str) -> StdResult<Uint128> {
+        match self {
+            AssetInfo::Cw20 { contract_addr } => {
+                let res: cw20::BalanceResponse = querier.query_wasm_smart(
+                    contract_addr,
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )?;
+                Ok(res.balance)
+            }
+            AssetInfo::Native { denom } => Ok(querier.query_balance(address, denom)?.amount),
+        }
+    }
+
+    /// Amount of a native asset attached to the message, zero for cw20 assets
+    pub fn sent_amount(&self, info: &MessageInfo) -> Uint128 {
+        match self {
+            AssetInfo::Cw20 { .. } => Uint128::zero(),
+            AssetInfo::Native { denom } => info
+                .funds
+                .iter()
+                .filter(|coin| &coin.denom == denom)
+                .map(|coin| coin.amount)
+                .sum(),
+        }
+    }
+}
+
+impl fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Asset: An amount of an asset
+#[cw_serde]
+pub struct Asset {
+    pub info: AssetInfo,
+    pub amount: Uint128,
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.info)
+    }
+}
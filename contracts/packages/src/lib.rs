@@ -1,2 +1,3 @@
+pub mod asset;
 pub mod vault_msg;
-pub mod pool_msg;
\ No newline at end of file
+pub mod pool_msg;
@@ -1,10 +1,20 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Binary, Uint128};
-use cw20::{AllowanceResponse, BalanceResponse, TokenInfoResponse};
+use cw20::{
+    AllowanceResponse, BalanceResponse, DownloadLogoResponse, Logo, MarketingInfoResponse,
+    TokenInfoResponse,
+};
+use cw20_base::msg::InstantiateMarketingInfo;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+/// Denominator of swap fees expressed in basis points
+pub const FEE_DENOMINATOR: u16 = 10_000;
+
+/// Highest swap fee a pool can be created or updated with (10%)
+pub const MAX_FEE_BPS: u16 = 1_000;
+
 #[cw_serde]
 pub struct PoolInstantiateMsg {
     /// name of the derivative token
@@ -13,6 +23,21 @@ pub struct PoolInstantiateMsg {
     pub symbol: String,
     /// decimal places of the derivative token (for UI)
     pub decimals: u8,
+    /// swap fee of the pool in basis points
+    pub fee_bps: u16,
+    /// cw20 marketing info of the derivative token, a logo requires a marketing address
+    pub marketing: Option<InstantiateMarketingInfo>,
+}
+
+/// Instantiate message of stableswap pools, the LP token fields plus the amplification coefficient
+#[cw_serde]
+pub struct StablePoolInstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub fee_bps: u16,
+    pub marketing: Option<InstantiateMarketingInfo>,
+    pub amp: u64,
 }
 
 /// Message type for `execute` entry_pointa
@@ -25,6 +50,22 @@ pub enum PoolExecuteMsg {
         amount: Uint128,
         msg: Binary,
     },
+    /// Updates the swap fee of the pool, only callable by the pool's governance (its factory)
+    UpdateFee {
+        fee_bps: u16,
+    },
+    /// Updates the marketing info of the LP token, only callable by its marketing address
+    UpdateMarketing {
+        project: Option<String>,
+        description: Option<String>,
+        marketing: Option<String>,
+    },
+    /// Uploads the logo of the LP token, only callable by its marketing address
+    UploadLogo(Logo),
+    /// Burns LP tokens of the vault after minting the protocol fee, only callable by the vault of the pool's factory
+    BurnFromVault {
+        amount: Uint128,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -35,24 +76,15 @@ pub struct Cw20ReceiveMsg {
     pub msg: Binary,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-#[serde(rename_all = "snake_case")]
-pub struct RemoveLiquidityParams {
-    pub token_a: String,
-    pub token_b: String,
-    pub reserve_a: Uint128,
-    pub reserve_b: Uint128,
-    pub amount_a: Uint128,
-    pub amount_b: Uint128,
-    pub address_to: String,
-}
-
 #[cw_serde] 
 pub struct RemoveLiquidityPoolParams {
     pub vault_contract_addresss: String,
     pub amount_a_min: Uint128,
     pub amount_b_min: Uint128,
-    pub address_to: String
+    pub address_to: String,
+    pub deadline: Uint128,
+    /// Withdraws into a single token of the pool, the vault swaps the other one for it
+    pub single_token_out: Option<SingleTokenOut>,
 }
 
 impl fmt::Display for Cw20ReceiveMsg {
@@ -69,7 +101,7 @@ impl fmt::Display for Cw20ReceiveMsg {
 
 /// Message type for `migrate` entry_point
 #[cw_serde]
-pub enum PoolMigrateMsg {}
+pub struct PoolMigrateMsg {}
 
 /// Message type for `query` entry_point
 #[cw_serde]
@@ -92,27 +124,46 @@ pub enum PoolQueryMsg {
 
     #[returns(GetAmountTokenTransfer)]
     GetAmountTransferToken { vault_address: String },
+
+    #[returns(FeeInfoResponse)]
+    FeeInfo {},
+
+    #[returns(MarketingInfoResponse)]
+    MarketingInfo {},
+
+    #[returns(DownloadLogoResponse)]
+    DownloadLogo {},
 }
 
 #[cw_serde]
 pub enum VaultMsgEnums {
     QueryPoolData { pool_address: String },
+    FeeTo {},
 }
 
+/// Query of the factory that instantiated the pool
 #[cw_serde]
-pub struct PoolDataResponse {
-    pub registered: bool,
-    pub token0: String,
-    pub token1: String,
-    pub reserve0: Uint128,
-    pub reserve1: Uint128,
+pub enum FactoryMsgEnums {
+    Config {},
+}
+
+/// Part of the factory config read by the pool, the other fields are ignored
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FactoryConfigResponse {
+    pub vault_contract: String,
 }
 
+pub use crate::vault_msg::{PoolDataResponse, SingleTokenOut};
+
 #[cw_serde]
 pub struct MintRecieveParams {
     pub to: String,
     pub amount0: Uint128,
     pub amount1: Uint128,
+    /// Hook message of the cw20 `Send` delivering the LP tokens to `to`, they are minted to it when unset
+    pub on_receive: Option<Binary>,
+    /// Minimum of LP tokens to mint, the mint fails below it
+    pub min_liquidity: Option<Uint128>,
 }
 
 #[cw_serde]
@@ -134,3 +185,10 @@ pub struct GetAmountTokenTransfer {
     pub amount_b: Uint128,
 }
 
+#[cw_serde]
+pub struct FeeInfoResponse {
+    /// swap fee of the pool in basis points
+    pub fee_bps: u16,
+    /// address allowed to update the fee
+    pub governance: String,
+}
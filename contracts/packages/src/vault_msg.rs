@@ -1,5 +1,6 @@
+use crate::asset::{Asset, AssetInfo};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Uint128, Binary};
+use cosmwasm_std::{Binary, Decimal, Decimal256, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -20,8 +21,8 @@ pub enum VaultExecuteMsg {
      *
      * Parameters are defined in RegisterPoolParams:
      * - `pool_address`: The address of the pool contract to be registered.
-     * - `token0`: The address or identifier of the first token in the token pair managed by the pool.
-     * - `token1`: The address or identifier of the second token in the token pair managed by the pool.
+     * - `token0`: The `AssetInfo` of the first token in the token pair managed by the pool.
+     * - `token1`: The `AssetInfo` of the second token in the token pair managed by the pool.
      * - `lp_token_contract`: The address of the CW20 contract responsible for minting LP tokens when users provide liquidity to the pool.
      *
      * In a liquidity pool, `token0` and `token1` represent a token pair that the pool manages. Users can provide liquidity in the form of both `token0` and `token1`, and in return, they receive LP (Liquidity Provider) tokens from the `lp_token_contract`.
@@ -33,39 +34,23 @@ pub enum VaultExecuteMsg {
      *
      * Parameters are defined in AddLiquidityParams:
      * - `pool_address`: The address of the pool contract where liquidity will be added.
-     * - `token_a`: The address or identifier of the first token to be contributed.
-     * - `token_b`: The address or identifier of the second token to be contributed.
+     * - `token_a`: The `AssetInfo` of the first token to be contributed.
+     * - `token_b`: The `AssetInfo` of the second token to be contributed.
      * - `amount_a_desired`: The desired amount of `token_a` to be contributed by the user.
      * - `amount_b_desired`: The desired amount of `token_b` to be contributed by the user.
      * - `amount_a_min`: The minimum amount of `token_a` acceptable for the contribution.
      * - `amount_b_min`: The minimum amount of `token_b` acceptable for the contribution.
      * - `address_to`: The recipient's address for receiving LP tokens.
-     * - `deadline`: The deadline by which the liquidity addition must occur.
+     * - `deadline`: The deadline by which the liquidity addition must occur, in unix seconds.
      *
      * This function allows users to provide liquidity to a pool by specifying the tokens they want to
      * contribute, the desired amounts, and minimum acceptable amounts. It also specifies the recipient's
      * address for receiving LP (Liquidity Provider) tokens and a deadline for the operation.
+     * cw20 tokens are pulled with `TransferFrom`, native tokens must be attached as funds for their
+     * desired amount and the part that isn't deposited is refunded.
      */
     AddLiquidity(AddLiquidityParams),
 
-    /**
-     * 3. RemoveLiquidity: This function allows a user to remove liquidity from a specific pool contract.
-     *
-     * Parameters are defined in RemoveLiquidityParams:
-     * - `pool_address`: The address of the pool contract from which liquidity will be removed.
-     * - `token_a`: The address or identifier of the first token in the liquidity pool.
-     * - `token_b`: The address or identifier of the second token in the liquidity pool.
-     * - `amount_a_min`: The minimum amount of `token_a` that the user is willing to receive.
-     * - `amount_b_min`: The minimum amount of `token_b` that the user is willing to receive.
-     * - `address_to`: The recipient's address for receiving the tokens withdrawn from the liquidity pool.
-     * - `deadline`: The deadline by which the liquidity removal must occur.
-     *
-     * This function allows users to remove liquidity from a pool by specifying the pool address,
-     * the tokens they want to withdraw, the minimum acceptable amounts of each token, the recipient's
-     * address for receiving the tokens, and a deadline for the operation.
-     */
-    RemoveLiquidity(RemoveLiquidityParams),
-
     /**
      * 4. SwapTokens: This function allows a user to swap one token for another within a specific pool contract.
      *
@@ -76,46 +61,246 @@ pub enum VaultExecuteMsg {
      * - `token_in`: The address or identifier of the input token.
      * - `token_out`: The address or identifier of the output token.
      * - `address_to`: The recipient's address for receiving the swapped tokens.
+     * - `deadline`: The deadline by which the swap must occur, in unix seconds.
+     * - `max_price_impact`: Optional bound on the price impact of the swap, e.g. `0.01` for 1%.
+     *
+     * The swap fails when the output is below `amount_out_min`, or when its price is further than
+     * `max_price_impact` from the spot price of the pool before the swap.
      *
      * Users can use this function to swap one token for another within a liquidity pool, specifying the pool address,
      * the amount of input token to be swapped, the minimum amount of output token expected, the input and output token
      * addresses, and the recipient's address for receiving the swapped tokens.
+     * The message of the cw20 `Send` is a `VaultHookMsg`, it can also swap through a path of pools or for an
+     * exact output.
      */
     Receive(Cw20ReceiveMsg),
+
+    /**
+     * 5. Swap: Swaps a native token attached as funds, with the same parameters as the cw20 swap.
+     * Exactly one coin of the `token_in` denom must be attached, its amount is the amount swapped.
+     */
+    Swap(SwapTokensParams),
+
+    /**
+     * 6. SetGuardian: Sets or removes the guardian, an address allowed to pause the vault next to its owner.
+     * Only the owner of the vault contract can call this function.
+     */
+    SetGuardian { guardian: Option<String> },
+
+    /**
+     * 7. SetPause: Halts or resumes swaps and deposits, for every pool or for the registered pool
+     * `pool_address`. Withdrawals are never paused. Only the owner or the guardian can call this function.
+     *
+     * Parameters:
+     * - `pool_address`: The pool to pause, `None` sets the global pause that applies to every pool.
+     * - `status`: What is paused, a pool is halted when either the global or its own pause halts it.
+     */
+    SetPause {
+        pool_address: Option<String>,
+        status: PauseStatus,
+    },
+
+    /**
+     * 8. MultiHopSwap: Swaps a native token attached as funds through a path of registered pools.
+     *
+     * Parameters are defined in MultiHopSwapParams:
+     * - `token_in`: The `AssetInfo` of the input token.
+     * - `operations`: The hops of the path in order, each one swaps the output of the previous hop in
+     *   `pool_address` for `token_out`. At most `MAX_SWAP_HOPS` hops.
+     * - `amount_out_min`: The minimum amount of the final token expected from the whole path.
+     * - `address_to`: The recipient's address for receiving the final token.
+     * - `deadline`: The deadline by which the swap must occur, in unix seconds.
+     *
+     * cw20 tokens are swapped through a path by sending them with a `VaultHookMsg::MultiHopSwap` hook instead.
+     */
+    MultiHopSwap(MultiHopSwapParams),
+
+    /**
+     * 9. SwapExactOut: Swaps a native token attached as funds for an exact amount of the output token.
+     *
+     * Parameters are defined in SwapExactOutParams:
+     * - `pool_address`: The address of the pool contract where the swap will occur.
+     * - `token_in`: The `AssetInfo` of the input token.
+     * - `token_out`: The `AssetInfo` of the output token.
+     * - `amount_out`: The amount of the output token wanted.
+     * - `amount_in_max`: The maximum amount of the input token to be swapped.
+     * - `address_to`: The recipient's address for receiving the swapped tokens.
+     * - `deadline`: The deadline by which the swap must occur, in unix seconds.
+     *
+     * The input is computed with the `GetAmountIn` of the pool, only that much of the amount sent is
+     * swapped and the remainder is refunded. cw20 tokens are sent with a `VaultHookMsg::SwapExactOut` hook instead.
+     */
+    SwapExactOut(SwapExactOutParams),
+
+    /**
+     * 10. FlashLoan: Lends assets out of the reserves of registered pools for the duration of the message.
+     *
+     * Parameters:
+     * - `assets`: The assets to borrow, each one out of the reserves of its `pool_address`. A token can only be
+     *   borrowed once per pool.
+     * - `recipient`: The contract receiving the assets, it is then called with `FlashLoanReceiverMsg`.
+     * - `msg`: Passed back to the recipient in the callback.
+     *
+     * By the end of the callback the recipient must have sent every asset back to the vault, plus a fee at the
+     * swap fee of its pool, rounded up. The fee is credited to the reserves of the pool. The vault can't be called while a
+     * loan is open, and pools with paused swaps don't lend. No funds can be attached to the message.
+     */
+    FlashLoan {
+        assets: Vec<FlashLoanAsset>,
+        recipient: String,
+        msg: Binary,
+    },
+
+    /**
+     * 11. SetFeeTo: Turns the protocol fee on by setting the address receiving it, or off with `None`.
+     * Only the owner of the vault contract can call this function.
+     *
+     * While it is on, every pool mints `fee_to` 1/6th of the growth of its liquidity from swap fees, each time
+     * liquidity is added or removed.
+     */
+    SetFeeTo { fee_to: Option<String> },
+
+    /**
+     * 12. Sync: Resets the recorded reserves of `pool_address` to what the vault holds for it, that is its
+     * balance of each token of the pool minus the reserves of the other pools and the pending deposits in that token.
+     * Only the owner of the vault contract can call this function.
+     */
+    Sync { pool_address: String },
+
+    /**
+     * 13. Skim: Sends `to` the balance of the vault in the tokens of `pool_address` that isn't part of the
+     * reserves of any pool or of a pending deposit, e.g. tokens transferred directly to the vault.
+     * Only the owner of the vault contract can call this function.
+     */
+    Skim { pool_address: String, to: String },
+
+    /**
+     * 14. ProvidePending: Provides the pending deposit of the sender into `pool_address` as liquidity, the
+     * native tokens attached to the message are added to it first.
+     *
+     * cw20 tokens join the pending deposit with a `VaultHookMsg::DepositPending` hook instead of an allowance,
+     * the last of them can be sent with a `VaultHookMsg::ProvidePending` hook to provide it in the same
+     * transaction. The part of the deposit that doesn't match the price of the pool is refunded.
+     */
+    ProvidePending(ProvidePendingParams),
+
+    /**
+     * 15. WithdrawPending: Sends the sender back its pending deposit into `pool_address`. It is never paused.
+     */
+    WithdrawPending { pool_address: String },
+
+    /**
+     * 16. ProvideSingleSided: Provides liquidity from the native `token_in` attached to the message alone, a
+     * part of it is swapped through the pool for its other token first.
+     *
+     * The part swapped is the root of the constant product with the swap fee, so that the rest of `token_in`
+     * and the swap output match the price of the pool after the swap. The deposit fails when less than
+     * `min_liquidity` LP tokens are minted. cw20 tokens use a `VaultHookMsg::ProvideSingleSided` hook.
+     */
+    ProvideSingleSided(ProvideSingleSidedParams),
+}
+
+/// VaultHookMsg: Actions triggered by cw20 tokens sent to the vault with `Send`, the sent tokens are the input
+#[cw_serde]
+pub enum VaultHookMsg {
+    /// Swaps the sent tokens through a single pool
+    Swap(SwapTokensParams),
+    /// Swaps the sent tokens through a path of pools
+    MultiHopSwap(MultiHopSwapParams),
+    /// Swaps the sent tokens for an exact output, the unused part of them is refunded
+    SwapExactOut(SwapExactOutParams),
+    /// Adds the sent tokens to the pending deposit of the sender into `pool_address`
+    DepositPending { pool_address: String },
+    /// Adds the sent tokens to the pending deposit of the sender and provides it as liquidity
+    ProvidePending(ProvidePendingParams),
+    /// Provides liquidity from the sent tokens alone, swapping a part of them for the other token of the pool
+    ProvideSingleSided(ProvideSingleSidedParams),
+    /**
+     * Removes liquidity with the sent LP tokens, the token of a registered pool. The vault computes the
+     * withdrawn amounts from its reserves of the pool and the LP supply after the pool burned them, the
+     * protocol fee is minted before the burn.
+     */
+    WithdrawLiquidity(WithdrawLiquidityParams),
+}
+
+/// Callback sent by the vault to the recipient of a flash loan
+#[cw_serde]
+pub enum FlashLoanReceiverMsg {
+    FlashLoanCallback {
+        /// Assets lent, to be sent back to the vault
+        assets: Vec<FlashLoanAsset>,
+        /// Fees owed on top of the assets, in the same order
+        fees: Vec<Asset>,
+        msg: Binary,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct RegisterPoolParams {
     pub pool_address: String,
-    pub token0: String,
-    pub token1: String,
+    pub token0: AssetInfo,
+    pub token1: AssetInfo,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct AddLiquidityParams {
     pub pool_address: String,
-    pub token_a: String,
-    pub token_b: String,
+    pub token_a: AssetInfo,
+    pub token_b: AssetInfo,
     pub amount_a_desired: Uint128,
     pub amount_b_desired: Uint128,
     pub amount_a_min: Uint128,
     pub amount_b_min: Uint128,
     pub address_to: String,
     pub deadline: Uint128,
+    /// When set, the LP tokens are delivered to `address_to` with cw20 `Send` and this hook message
+    pub on_receive: Option<Binary>,
+    /// Optional minimum of LP tokens to mint, the deposit fails below it
+    pub min_liquidity: Option<Uint128>,
 }
 
+/// ProvideSingleSidedParams: `token_in` is the only token deposited, it must be one of the tokens of the pool
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
-pub struct RemoveLiquidityParams {
-    pub token_a: String,
-    pub token_b: String,
-    pub reserve_a: Uint128,
-    pub reserve_b: Uint128,
-    pub amount_a: Uint128,
-    pub amount_b: Uint128,
+pub struct ProvideSingleSidedParams {
+    pub pool_address: String,
+    pub token_in: AssetInfo,
+    pub min_liquidity: Uint128,
     pub address_to: String,
+    pub deadline: Uint128,
+    pub on_receive: Option<Binary>,
+}
+
+/// WithdrawLiquidityParams: The minimums are in token0 and token1 of the pool, before any `single_token_out` swap
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct WithdrawLiquidityParams {
+    pub amount0_min: Uint128,
+    pub amount1_min: Uint128,
+    pub address_to: String,
+    pub deadline: Uint128,
+    pub single_token_out: Option<SingleTokenOut>,
+}
+
+/// ProvidePendingParams: The minimums are in token0 and token1 of the pool
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct ProvidePendingParams {
+    pub pool_address: String,
+    pub amount0_min: Uint128,
+    pub amount1_min: Uint128,
+    pub address_to: String,
+    pub deadline: Uint128,
+    pub on_receive: Option<Binary>,
+}
+
+/// SingleTokenOut: Withdraws into `token_out` alone, at least `min_out` of it
+#[cw_serde]
+pub struct SingleTokenOut {
+    pub token_out: AssetInfo,
+    pub min_out: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -141,12 +326,55 @@ impl fmt::Display for Cw20ReceiveMsg {
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct SwapTokensParams {
-    pub message: String,
     pub pool_address: String,
     pub amount_out_min: Uint128,
-    pub token_in: String,
-    pub token_out: String,
+    pub token_in: AssetInfo,
+    pub token_out: AssetInfo,
+    pub address_to: String,
+    pub deadline: Uint128,
+    pub max_price_impact: Option<Decimal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SwapExactOutParams {
+    pub pool_address: String,
+    pub token_in: AssetInfo,
+    pub token_out: AssetInfo,
+    pub amount_out: Uint128,
+    pub amount_in_max: Uint128,
+    pub address_to: String,
+    pub deadline: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct FlashLoanAsset {
+    pub pool_address: String,
+    pub asset: Asset,
+}
+
+/// Maximum number of pools a multi hop swap can go through
+pub const MAX_SWAP_HOPS: usize = 4;
+
+/// Longest time weighted average price window, in seconds. Older price observations are pruned
+pub const MAX_TWAP_WINDOW: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SwapOperation {
+    pub pool_address: String,
+    pub token_out: AssetInfo,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct MultiHopSwapParams {
+    pub token_in: AssetInfo,
+    pub operations: Vec<SwapOperation>,
+    pub amount_out_min: Uint128,
     pub address_to: String,
+    pub deadline: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -170,18 +398,190 @@ pub struct ExecutePoolReplyData {
     pub reserve_b: Uint128,
 }
 
+#[cw_serde]
+#[derive(Default, Copy)]
+pub struct PauseStatus {
+    pub swaps: bool,
+    pub deposits: bool,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum VaultQueryMsg {
     #[returns(PoolDataResponse)]
     QueryPoolData { pool_address: String },
+
+    /// Lists the registered pools, ordered by pool contract address
+    #[returns(PoolsResponse)]
+    AllPools {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Lists the registered pools holding `token` on either side, ordered by pool contract address
+    #[returns(PoolsResponse)]
+    PoolsByToken {
+        token: AssetInfo,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Owner of the vault and a page of the factory contracts registered in it, ordered by address
+    #[returns(ConfigResponse)]
+    Config {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Whether `factory_address` is a registered factory contract
+    #[returns(FactoryRegisteredResponse)]
+    FactoryRegistered { factory_address: String },
+
+    /// Pending deposits of `depositor`, into `pool_address` only when given
+    #[returns(PendingDepositsResponse)]
+    PendingDeposits {
+        depositor: String,
+        pool_address: Option<String>,
+    },
+
+    /// Pause state of the vault, and of `pool_address` when given
+    #[returns(PauseInfoResponse)]
+    PauseInfo { pool_address: Option<String> },
+
+    /// Sum of the reserves of every pool per token, compared with the balances of the vault
+    #[returns(SolvencyResponse)]
+    Solvency {},
+
+    /// Address receiving the protocol fee of the pools, `None` when the fee is off
+    #[returns(FeeToResponse)]
+    FeeTo {},
+
+    /// Simulates a swap of `offer_asset` for the other token of the pool, using the reserves of the vault
+    #[returns(SwapSimulationResponse)]
+    SimulateSwap {
+        pool_address: String,
+        offer_asset: Asset,
+    },
+
+    /// Simulates the swap needed to get exactly `ask_asset` out of the pool, using the reserves of the vault
+    #[returns(SwapSimulationResponse)]
+    ReverseSimulateSwap {
+        pool_address: String,
+        ask_asset: Asset,
+    },
+
+    /// Time weighted average prices of the pool between `start_time` and `end_time`, in unix seconds.
+    /// `start_time` can be at most `MAX_TWAP_WINDOW` seconds ago
+    #[returns(TwapResponse)]
+    Twap {
+        pool_address: String,
+        start_time: u64,
+        end_time: u64,
+    },
+}
+
+#[cw_serde]
+pub struct PoolResponse {
+    pub pool_address: String,
+    pub pool_data: PoolDataResponse,
+}
+
+#[cw_serde]
+pub struct PoolsResponse {
+    pub pools: Vec<PoolResponse>,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub owner: String,
+    pub factories: Vec<String>,
+}
+
+#[cw_serde]
+pub struct FactoryRegisteredResponse {
+    pub registered: bool,
+}
+
+#[cw_serde]
+pub struct PendingDeposit {
+    pub pool_address: String,
+    pub assets: Vec<Asset>,
+}
+
+#[cw_serde]
+pub struct PendingDepositsResponse {
+    pub deposits: Vec<PendingDeposit>,
+}
+
+#[cw_serde]
+pub struct TwapResponse {
+    pub token0: AssetInfo,
+    pub token1: AssetInfo,
+    /// Average price of token0 in token1
+    pub price0_average: Decimal256,
+    /// Average price of token1 in token0
+    pub price1_average: Decimal256,
+}
+
+#[cw_serde]
+pub struct TokenSolvency {
+    pub token: AssetInfo,
+    /// Sum of the reserves of every pool in the token
+    pub reserves: Uint128,
+    /// Balance of the vault in the token
+    pub balance: Uint128,
+    /// Sum of the pending deposits in the token, held outside of the reserves
+    pub pending: Uint128,
+}
+
+#[cw_serde]
+pub struct SolvencyResponse {
+    /// Whether the vault holds at least the reserves and the pending deposits of every token
+    pub solvent: bool,
+    pub tokens: Vec<TokenSolvency>,
+}
+
+#[cw_serde]
+pub struct FeeToResponse {
+    pub fee_to: Option<String>,
+}
+
+#[cw_serde]
+pub struct SwapSimulationResponse {
+    pub amount_in: Uint128,
+    pub amount_out: Uint128,
+    /// Swap fee paid, in the input token
+    pub fee_amount: Uint128,
+    /// Price of the input token in the output token, before and after the swap
+    pub spot_price_before: Decimal,
+    pub spot_price_after: Decimal,
+    pub price_impact: Decimal,
+}
+
+#[cw_serde]
+pub struct PauseInfoResponse {
+    pub guardian: Option<String>,
+    /// Pause applying to every pool
+    pub global: PauseStatus,
+    /// Pause of the given pool only
+    pub pool: Option<PauseStatus>,
+    /// What is halted for the given pool, or globally when no pool is given
+    pub effective: PauseStatus,
 }
 
 #[cw_serde]
 pub struct PoolDataResponse {
     pub registered: bool,
-    pub token0: String,
-    pub token1: String,
+    pub token0: AssetInfo,
+    pub token1: AssetInfo,
     pub reserve0: Uint128,
-    pub reserve1: Uint128
+    pub reserve1: Uint128,
+    /// Sums of the price of token0 in token1 and of token1 in token0 times the seconds it held
+    #[serde(default)]
+    pub price0_cumulative_last: Decimal256,
+    #[serde(default)]
+    pub price1_cumulative_last: Decimal256,
+    /// Block time of the last reserve change, in unix seconds
+    #[serde(default)]
+    pub block_time_last: u64,
 }
\ No newline at end of file
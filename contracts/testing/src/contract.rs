@@ -50,23 +50,41 @@ pub fn reply(_deps: DepsMut, _env: Env, _msg: Reply) -> Result<Response, Contrac
 
 #[cfg(test)]
 mod vault_tests {
-    use cosmwasm_std::{to_binary, Addr, Empty, Uint128};
-    use cw_multi_test::{App, ContractWrapper, Executor};
+    use super::helpers::{
+        cw20_asset, deadline, mock_app, native_asset, setup, TestApp, NATIVE_DENOM,
+    };
+    use cosmwasm_std::{
+        coins, from_binary, to_binary, Addr, Binary, Decimal, Decimal256, Deps, DepsMut, Empty,
+        Env, MessageInfo, Response, StdResult, Uint128, WasmMsg,
+    };
+    use cw_multi_test::{ContractWrapper, Executor};
+    use factory::msg::PoolType;
+    use packages::asset::{Asset, AssetInfo};
+    use packages::pool_msg::{PoolExecuteMsg, PoolQueryMsg};
+    use packages::vault_msg::{
+        AddLiquidityParams, ConfigResponse, Cw20ReceiveMsg, FactoryRegisteredResponse,
+        FeeToResponse, FlashLoanAsset, FlashLoanReceiverMsg, MultiHopSwapParams, PauseInfoResponse,
+        PauseStatus, PendingDeposit, PendingDepositsResponse, PoolDataResponse, PoolsResponse,
+        ProvidePendingParams, ProvideSingleSidedParams, SingleTokenOut, SolvencyResponse,
+        SwapExactOutParams, SwapOperation, SwapSimulationResponse, SwapTokensParams, TokenSolvency,
+        TwapResponse, VaultExecuteMsg, VaultHookMsg, VaultQueryMsg, WithdrawLiquidityParams,
+        MAX_TWAP_WINDOW,
+    };
 
     #[test]
     fn execute_vault_test() {
-        /* addresses for creating transactions */
-        let vault_owner = Addr::unchecked("vault_owner");
-        let usdc_owner = Addr::unchecked("usdc_owner");
-        let usdc_minter = Addr::unchecked("usdc_minter");
-        let usdt_owner = Addr::unchecked("usdt_owner");
-        let usdt_minter = Addr::unchecked("usdt_minter");
-        let factory_owner = Addr::unchecked("factory_owner");
+        /* initializing app */
+        let mut app = mock_app();
 
-        let liquidity_provider = Addr::unchecked("liquidity_provider");
+        /* addresses for creating transactions */
+        let vault_owner = app.api().addr_make("vault_owner");
+        let usdc_owner = app.api().addr_make("usdc_owner");
+        let usdc_minter = app.api().addr_make("usdc_minter");
+        let usdt_owner = app.api().addr_make("usdt_owner");
+        let usdt_minter = app.api().addr_make("usdt_minter");
+        let factory_owner = app.api().addr_make("factory_owner");
 
-        /* initializing app */
-        let mut app = App::default();
+        let liquidity_provider = app.api().addr_make("liquidity_provider");
 
         /* vault contract */
         let code = ContractWrapper::new(
@@ -108,7 +126,7 @@ mod vault_tests {
                     decimals: 6,
                     initial_balances: vec![],
                     mint: Some(cw20::MinterResponse {
-                        minter: "usdc_minter".to_string(),
+                        minter: usdc_minter.to_string(),
                         cap: None,
                     }),
                     marketing: None,
@@ -124,7 +142,7 @@ mod vault_tests {
             usdc_minter.clone(),
             usdc20.clone(),
             &cw20_base::msg::ExecuteMsg::Mint {
-                recipient: "liquidity_provider".to_string(),
+                recipient: liquidity_provider.to_string(),
                 amount: Uint128::from(1000000u128),
             },
             &[],
@@ -135,7 +153,7 @@ mod vault_tests {
             .query_wasm_smart(
                 usdc20.clone(),
                 &cw20_base::msg::QueryMsg::Balance {
-                    address: "liquidity_provider".to_string(),
+                    address: liquidity_provider.to_string(),
                 },
             )
             .unwrap();
@@ -158,7 +176,7 @@ mod vault_tests {
                     decimals: 6,
                     initial_balances: vec![],
                     mint: Some(cw20::MinterResponse {
-                        minter: "usdt_minter".to_string(),
+                        minter: usdt_minter.to_string(),
                         cap: None,
                     }),
                     marketing: None,
@@ -174,7 +192,7 @@ mod vault_tests {
             usdt_minter.clone(),
             usdt20.clone(),
             &cw20_base::msg::ExecuteMsg::Mint {
-                recipient: "liquidity_provider".to_string(),
+                recipient: liquidity_provider.to_string(),
                 amount: Uint128::from(1000000u128),
             },
             &[],
@@ -185,7 +203,7 @@ mod vault_tests {
             .query_wasm_smart(
                 usdt20.clone(),
                 &cw20_base::msg::QueryMsg::Balance {
-                    address: "liquidity_provider".to_string(),
+                    address: liquidity_provider.to_string(),
                 },
             )
             .unwrap();
@@ -218,7 +236,10 @@ mod vault_tests {
                 factory_code_id,
                 factory_owner.clone(),
                 &factory::msg::InstantiateMsg {
-                    pool_contract_code_id: pool_code_id,
+                    pool_code_ids: vec![factory::msg::PoolCodeId {
+                        pool_type: factory::msg::PoolType::ConstantProduct,
+                        code_id: pool_code_id,
+                    }],
                     vault_contract: vault_contract_address.to_string().clone(),
                 },
                 &[],
@@ -244,11 +265,14 @@ mod vault_tests {
 
         // factory execute creating pool
         let execute_create_pool_tx = app.execute_contract(
-            Addr::unchecked("fac"),
+            app.api().addr_make("fac"),
             factory_contract_address,
             &factory::msg::ExecuteMsg::CreatePool {
-                token_a: usdc20.to_string(),
-                token_b: usdt20.to_string(),
+                token_a: cw20_asset(&usdc20),
+                token_b: cw20_asset(&usdt20),
+                pool_type: factory::msg::PoolType::ConstantProduct,
+                fee_bps: 30,
+                params: None,
             },
             &[],
         );
@@ -315,14 +339,16 @@ mod vault_tests {
                                         &packages::vault_msg::VaultExecuteMsg::AddLiquidity(
                                             packages::vault_msg::AddLiquidityParams {
                                                 pool_address: data.value.to_string().clone(),
-                                                token_a: usdc20.to_string().clone(),
-                                                token_b: usdt20.to_string().clone(),
+                                                token_a: cw20_asset(&usdc20),
+                                                token_b: cw20_asset(&usdt20),
                                                 amount_a_desired: Uint128::from(10000u128),
                                                 amount_b_desired: Uint128::from(9000u128),
                                                 amount_a_min: Uint128::from(9999u128),
                                                 amount_b_min: Uint128::from(8999u128),
                                                 address_to: liquidity_provider.to_string().clone(),
-                                                deadline: Uint128::from(1u128),
+                                                deadline: deadline(&app),
+                                                on_receive: None,
+                                                min_liquidity: None,
                                             },
                                         ),
                                         &[],
@@ -400,14 +426,16 @@ mod vault_tests {
                                         &packages::vault_msg::VaultExecuteMsg::AddLiquidity(
                                             packages::vault_msg::AddLiquidityParams {
                                                 pool_address: data.value.to_string().clone(),
-                                                token_a: usdc20.to_string().clone(),
-                                                token_b: usdt20.to_string().clone(),
+                                                token_a: cw20_asset(&usdc20),
+                                                token_b: cw20_asset(&usdt20),
                                                 amount_a_desired: Uint128::from(10000u128),
                                                 amount_b_desired: Uint128::from(9000u128),
                                                 amount_a_min: Uint128::from(100u128),
                                                 amount_b_min: Uint128::from(100u128),
                                                 address_to: liquidity_provider.to_string().clone(),
-                                                deadline: Uint128::from(1u128),
+                                                deadline: deadline(&app),
+                                                on_receive: None,
+                                                min_liquidity: None,
                                             },
                                         ),
                                         &[],
@@ -454,7 +482,7 @@ mod vault_tests {
                                     .query_wasm_smart(
                                         usdc20.clone(),
                                         &cw20_base::msg::QueryMsg::Balance {
-                                            address: "liquidity_provider".to_string(),
+                                            address: liquidity_provider.to_string(),
                                         },
                                     )
                                     .unwrap();
@@ -466,7 +494,7 @@ mod vault_tests {
                                     .query_wasm_smart(
                                         usdt20.clone(),
                                         &cw20_base::msg::QueryMsg::Balance {
-                                            address: "liquidity_provider".to_string(),
+                                            address: liquidity_provider.to_string(),
                                         },
                                     )
                                     .unwrap();
@@ -487,6 +515,8 @@ mod vault_tests {
                                                     amount_a_min: Uint128::from(50u128),
                                                     amount_b_min: Uint128::from(50u128),
                                                     address_to: liquidity_provider.to_string(),
+                                                    deadline: deadline(&app),
+                                                    single_token_out: None,
                                                 },
                                             )
                                             .unwrap(),
@@ -512,7 +542,7 @@ mod vault_tests {
                                     .query_wasm_smart(
                                         usdc20.clone(),
                                         &cw20_base::msg::QueryMsg::Balance {
-                                            address: "liquidity_provider".to_string(),
+                                            address: liquidity_provider.to_string(),
                                         },
                                     )
                                     .unwrap();
@@ -524,7 +554,7 @@ mod vault_tests {
                                     .query_wasm_smart(
                                         usdt20.clone(),
                                         &cw20_base::msg::QueryMsg::Balance {
-                                            address: "liquidity_provider".to_string(),
+                                            address: liquidity_provider.to_string(),
                                         },
                                     )
                                     .unwrap();
@@ -537,14 +567,17 @@ mod vault_tests {
                                     &cw20_base::msg::ExecuteMsg::Send {
                                         contract: vault_contract_address.clone().to_string(),
                                         amount: Uint128::from(100u128),
-                                        msg: to_binary(&packages::vault_msg::SwapTokensParams {
-                                            message: String::from("execute_swap_tokens"),
-                                            pool_address: data.value.clone().to_string(),
-                                            amount_out_min: Uint128::from(5u128),
-                                            token_in: usdt20.clone().to_string(),
-                                            token_out: usdc20.clone().to_string(),
-                                            address_to: liquidity_provider.to_string(),
-                                        })
+                                        msg: to_binary(&packages::vault_msg::VaultHookMsg::Swap(
+                                            packages::vault_msg::SwapTokensParams {
+                                                pool_address: data.value.clone().to_string(),
+                                                amount_out_min: Uint128::from(5u128),
+                                                token_in: cw20_asset(&usdt20),
+                                                token_out: cw20_asset(&usdc20),
+                                                address_to: liquidity_provider.to_string(),
+                                                deadline: deadline(&app),
+                                                max_price_impact: None,
+                                            },
+                                        ))
                                         .unwrap(),
                                     },
                                     &[],
@@ -558,8 +591,8 @@ mod vault_tests {
                                 //             pool_address: data.value.clone().to_string(),
                                 //             amount_in: Uint128::from(100u128),
                                 //             amount_out_min: Uint128::from(5u128),
-                                //             token_in: usdc20.clone().to_string(),
-                                //             token_out: usdt20.clone().to_string(),
+                                //             token_in: cw20_asset(&usdc20),
+                                //             token_out: cw20_asset(&usdt20),
                                 //             address_to: liquidity_provider.to_string(),
                                 //         },
                                 //     ),
@@ -571,7 +604,7 @@ mod vault_tests {
                                     .query_wasm_smart(
                                         usdc20.clone(),
                                         &cw20_base::msg::QueryMsg::Balance {
-                                            address: "liquidity_provider".to_string(),
+                                            address: liquidity_provider.to_string(),
                                         },
                                     )
                                     .unwrap();
@@ -583,7 +616,7 @@ mod vault_tests {
                                     .query_wasm_smart(
                                         usdt20.clone(),
                                         &cw20_base::msg::QueryMsg::Balance {
-                                            address: "liquidity_provider".to_string(),
+                                            address: liquidity_provider.to_string(),
                                         },
                                     )
                                     .unwrap();
@@ -601,4 +634,4164 @@ mod vault_tests {
             }
         }
     }
+
+    #[test]
+    fn native_and_cw20_pair() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        let token_info: cw20::TokenInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(pool.clone(), &PoolQueryMsg::TokenInfo {})
+            .unwrap();
+        assert!(token_info.symbol.contains("OSMO"));
+        assert_eq!(token_info.decimals, 6);
+
+        // a denom without bank metadata is symbolized after itself, in its base unit
+        let ion_pool = suite.create_pool_with(
+            native_asset("uion"),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+        let token_info: cw20::TokenInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(ion_pool, &PoolQueryMsg::TokenInfo {})
+            .unwrap();
+        assert!(token_info.symbol.contains("uion"));
+        assert_eq!(token_info.decimals, 3);
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(15_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let add_liquidity = |amount_osmo: u128, amount_usdc: u128| {
+            VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                pool_address: pool.to_string(),
+                token_a: osmo.clone(),
+                token_b: usdc_asset.clone(),
+                amount_a_desired: Uint128::from(amount_osmo),
+                amount_b_desired: Uint128::from(amount_usdc),
+                amount_a_min: Uint128::zero(),
+                amount_b_min: Uint128::zero(),
+                address_to: user.to_string(),
+                deadline,
+                on_receive: None,
+                min_liquidity: None,
+            })
+        };
+
+        // native tokens must be attached for their desired amount
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity(10_000, 10_000),
+                &coins(5_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::InvalidFunds {
+                val: format!("expected 10000{}", NATIVE_DENOM),
+            }
+            .to_string()
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity(10_000, 10_000),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        // only half of the attached native tokens are needed, the rest is refunded
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity(10_000, 5_000),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        let native_balance = |suite: &super::helpers::Suite, address: &Addr| {
+            suite
+                .app
+                .wrap()
+                .query_balance(address, NATIVE_DENOM)
+                .unwrap()
+                .amount
+        };
+        let usdc_balance = |suite: &super::helpers::Suite| -> Uint128 {
+            let res: cw20::BalanceResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    usdc.clone(),
+                    &cw20_base::msg::QueryMsg::Balance {
+                        address: user.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance
+        };
+        assert_eq!(native_balance(&suite, &user), Uint128::from(985_000u128));
+        assert_eq!(native_balance(&suite, &vault), Uint128::from(15_000u128));
+        assert_eq!(usdc_balance(&suite), Uint128::from(985_000u128));
+
+        // native to cw20 swap with attached funds
+        let swap_params = |token_in: &AssetInfo, token_out: &AssetInfo| SwapTokensParams {
+            pool_address: pool.to_string(),
+            amount_out_min: Uint128::zero(),
+            token_in: token_in.clone(),
+            token_out: token_out.clone(),
+            address_to: user.to_string(),
+            deadline,
+            max_price_impact: None,
+        };
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::Swap(swap_params(&osmo, &usdc_asset)),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        assert_eq!(native_balance(&suite, &user), Uint128::from(984_000u128));
+        assert_eq!(usdc_balance(&suite), Uint128::from(985_934u128));
+
+        // cw20 to native swap through the cw20 hook
+        // the hook message must be a `VaultHookMsg`
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::Send {
+                    contract: vault.to_string(),
+                    amount: Uint128::from(934u128),
+                    msg: to_binary(&swap_params(&usdc_asset, &osmo)).unwrap(),
+                },
+                &[],
+            )
+            .unwrap_err();
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::Send {
+                    contract: vault.to_string(),
+                    amount: Uint128::from(934u128),
+                    msg: to_binary(&VaultHookMsg::Swap(swap_params(&usdc_asset, &osmo))).unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(usdc_balance(&suite), Uint128::from(985_000u128));
+        assert_eq!(native_balance(&suite, &user), Uint128::from(984_993u128));
+
+        // the swapped tokens must belong to the pool
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::Swap(swap_params(&osmo, &cw20_asset(&suite.usdt))),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::AssetMismatch {}.to_string()
+        );
+    }
+
+    #[test]
+    fn pause_halts_swaps_and_deposits_but_not_withdrawals() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let vault_owner = suite.app.api().addr_make("vault_owner");
+        let guardian = suite.app.api().addr_make("guardian");
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(20_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let add_liquidity = VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+            pool_address: pool.to_string(),
+            token_a: osmo.clone(),
+            token_b: usdc_asset.clone(),
+            amount_a_desired: Uint128::from(10_000u128),
+            amount_b_desired: Uint128::from(10_000u128),
+            amount_a_min: Uint128::zero(),
+            amount_b_min: Uint128::zero(),
+            address_to: user.to_string(),
+            deadline,
+            on_receive: None,
+            min_liquidity: None,
+        });
+        let swap = VaultExecuteMsg::Swap(SwapTokensParams {
+            pool_address: pool.to_string(),
+            amount_out_min: Uint128::zero(),
+            token_in: osmo.clone(),
+            token_out: usdc_asset.clone(),
+            address_to: user.to_string(),
+            deadline,
+            max_price_impact: None,
+        });
+        let set_pause =
+            |pool_address: Option<&Addr>, swaps: bool, deposits: bool| VaultExecuteMsg::SetPause {
+                pool_address: pool_address.map(|pool| pool.to_string()),
+                status: PauseStatus { swaps, deposits },
+            };
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity,
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        // only the owner and the guardian can pause
+        let err = suite
+            .app
+            .execute_contract(
+                guardian.clone(),
+                vault.clone(),
+                &set_pause(None, true, false),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::Unauthorized {}.to_string()
+        );
+
+        suite
+            .app
+            .execute_contract(
+                vault_owner.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::SetGuardian {
+                    guardian: Some(guardian.to_string()),
+                },
+                &[],
+            )
+            .unwrap();
+
+        // a global swap pause halts swaps but not deposits
+        let res = suite
+            .app
+            .execute_contract(
+                guardian.clone(),
+                vault.clone(),
+                &set_pause(None, true, false),
+                &[],
+            )
+            .unwrap();
+        let wasm = res.events.iter().find(|event| event.ty == "wasm").unwrap();
+        assert!(wasm
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "pause_scope" && attr.value == "global"));
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &swap,
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::Paused {
+                action: String::from("Swaps"),
+            }
+            .to_string()
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity,
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        // a pool deposit pause adds to the global pause
+        suite
+            .app
+            .execute_contract(
+                guardian.clone(),
+                vault.clone(),
+                &set_pause(Some(&pool), false, true),
+                &[],
+            )
+            .unwrap();
+
+        let pause_info: PauseInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                vault.clone(),
+                &VaultQueryMsg::PauseInfo {
+                    pool_address: Some(pool.to_string()),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            pause_info,
+            PauseInfoResponse {
+                guardian: Some(guardian.to_string()),
+                global: PauseStatus {
+                    swaps: true,
+                    deposits: false,
+                },
+                pool: Some(PauseStatus {
+                    swaps: false,
+                    deposits: true,
+                }),
+                effective: PauseStatus {
+                    swaps: true,
+                    deposits: true,
+                },
+            }
+        );
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity,
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::Paused {
+                action: String::from("Deposits"),
+            }
+            .to_string()
+        );
+
+        // withdrawals stay open while everything is paused
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                pool.clone(),
+                &cw20_base::msg::ExecuteMsg::Send {
+                    contract: pool.to_string(),
+                    amount: Uint128::from(1_000u128),
+                    msg: to_binary(&packages::pool_msg::RemoveLiquidityPoolParams {
+                        vault_contract_addresss: vault.to_string(),
+                        amount_a_min: Uint128::zero(),
+                        amount_b_min: Uint128::zero(),
+                        address_to: user.to_string(),
+                        deadline,
+                        single_token_out: None,
+                    })
+                    .unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        // lifting the global pause resumes swaps on the pool
+        suite
+            .app
+            .execute_contract(
+                vault_owner.clone(),
+                vault.clone(),
+                &set_pause(None, false, false),
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &swap,
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn expired_deadlines_are_rejected() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(20_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let add_liquidity = VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+            pool_address: pool.to_string(),
+            token_a: osmo.clone(),
+            token_b: usdc_asset.clone(),
+            amount_a_desired: Uint128::from(10_000u128),
+            amount_b_desired: Uint128::from(10_000u128),
+            amount_a_min: Uint128::zero(),
+            amount_b_min: Uint128::zero(),
+            address_to: user.to_string(),
+            deadline,
+            on_receive: None,
+            min_liquidity: None,
+        });
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity,
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        // move the chain past the deadline
+        suite.app.update_block(|block| {
+            block.time = block.time.plus_seconds(61);
+            block.height += 12;
+        });
+        let expired = vault::ContractError::Expired {
+            deadline,
+            block_time: suite.app.block_info().time.seconds(),
+        }
+        .to_string();
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity,
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), expired);
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::Swap(SwapTokensParams {
+                    pool_address: pool.to_string(),
+                    amount_out_min: Uint128::zero(),
+                    token_in: osmo.clone(),
+                    token_out: usdc_asset.clone(),
+                    address_to: user.to_string(),
+                    deadline,
+                    max_price_impact: None,
+                }),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), expired);
+
+        // the deadline of a withdrawal is carried from the pool to the vault
+        let remove_liquidity = |deadline: Uint128| cw20_base::msg::ExecuteMsg::Send {
+            contract: pool.to_string(),
+            amount: Uint128::from(1_000u128),
+            msg: to_binary(&packages::pool_msg::RemoveLiquidityPoolParams {
+                vault_contract_addresss: vault.to_string(),
+                amount_a_min: Uint128::zero(),
+                amount_b_min: Uint128::zero(),
+                address_to: user.to_string(),
+                deadline,
+                single_token_out: None,
+            })
+            .unwrap(),
+        };
+
+        let err = suite
+            .app
+            .execute_contract(user.clone(), pool.clone(), &remove_liquidity(deadline), &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), expired);
+
+        let new_deadline = super::helpers::deadline(&suite.app);
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                pool.clone(),
+                &remove_liquidity(new_deadline),
+                &[],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn swap_slippage_and_price_impact_bounds() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(10_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(10_000u128),
+                    amount_b_desired: Uint128::from(10_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline,
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        // 1_000 uosmo in a 10_000 / 10_000 pool with a 0.3% fee gives 906 usdc, against 1_000 at the spot price
+        let swap = |amount_out_min: u128, max_price_impact: Option<Decimal>| {
+            VaultExecuteMsg::Swap(SwapTokensParams {
+                pool_address: pool.to_string(),
+                amount_out_min: Uint128::from(amount_out_min),
+                token_in: osmo.clone(),
+                token_out: usdc_asset.clone(),
+                address_to: user.to_string(),
+                deadline,
+                max_price_impact,
+            })
+        };
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &swap(907, None),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::SlippageExceeded {
+                amount_out: Uint128::from(906u128),
+                amount_out_min: Uint128::from(907u128),
+            }
+            .to_string()
+        );
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &swap(0, Some(Decimal::percent(5))),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::PriceImpactExceeded {
+                price_impact: Decimal::permille(94),
+                max_price_impact: Decimal::percent(5),
+            }
+            .to_string()
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &swap(906, Some(Decimal::percent(10))),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn multi_hop_swap_through_registered_pools() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault) = (suite.user.clone(), suite.vault.clone());
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+        let (osmo, usdc_asset, usdt_asset) = (
+            native_asset(NATIVE_DENOM),
+            cw20_asset(&usdc),
+            cw20_asset(&usdt),
+        );
+
+        let osmo_usdc = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+        let usdc_usdt = suite.create_pool(&usdc, &usdt);
+
+        for token in [&usdc, &usdt] {
+            suite
+                .app
+                .execute_contract(
+                    user.clone(),
+                    token.clone(),
+                    &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                        spender: vault.to_string(),
+                        amount: Uint128::from(20_000u128),
+                        expires: None,
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        for (pool, token_a, token_b, funds) in [
+            (&osmo_usdc, &osmo, &usdc_asset, coins(10_000, NATIVE_DENOM)),
+            (&usdc_usdt, &usdc_asset, &usdt_asset, vec![]),
+        ] {
+            suite
+                .app
+                .execute_contract(
+                    user.clone(),
+                    vault.clone(),
+                    &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                        pool_address: pool.to_string(),
+                        token_a: token_a.clone(),
+                        token_b: token_b.clone(),
+                        amount_a_desired: Uint128::from(10_000u128),
+                        amount_b_desired: Uint128::from(10_000u128),
+                        amount_a_min: Uint128::zero(),
+                        amount_b_min: Uint128::zero(),
+                        address_to: user.to_string(),
+                        deadline,
+                        on_receive: None,
+                        min_liquidity: None,
+                    }),
+                    &funds,
+                )
+                .unwrap();
+        }
+
+        let multi_hop_swap =
+            |token_in: &AssetInfo, operations: Vec<(&Addr, &AssetInfo)>, amount_out_min: u128| {
+                MultiHopSwapParams {
+                    token_in: token_in.clone(),
+                    operations: operations
+                        .into_iter()
+                        .map(|(pool, token_out)| SwapOperation {
+                            pool_address: pool.to_string(),
+                            token_out: token_out.clone(),
+                        })
+                        .collect(),
+                    amount_out_min: Uint128::from(amount_out_min),
+                    address_to: user.to_string(),
+                    deadline,
+                }
+            };
+        let usdt_balance = |suite: &super::helpers::Suite| -> Uint128 {
+            let res: cw20::BalanceResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    usdt.clone(),
+                    &cw20_base::msg::QueryMsg::Balance {
+                        address: user.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance
+        };
+
+        // 1_000 uosmo give 906 usdc in the first pool, which give 828 usdt in the second one
+        let path = vec![(&osmo_usdc, &usdc_asset), (&usdc_usdt, &usdt_asset)];
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::MultiHopSwap(multi_hop_swap(&osmo, path.clone(), 829)),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::SlippageExceeded {
+                amount_out: Uint128::from(828u128),
+                amount_out_min: Uint128::from(829u128),
+            }
+            .to_string()
+        );
+
+        let balance_before = usdt_balance(&suite);
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::MultiHopSwap(multi_hop_swap(&osmo, path, 828)),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        assert_eq!(
+            usdt_balance(&suite),
+            balance_before + Uint128::from(828u128)
+        );
+
+        // both pools are updated
+        for (pool, token_in, amount_in, amount_out) in [
+            (&osmo_usdc, &osmo, 1_000u128, 906u128),
+            (&usdc_usdt, &usdc_asset, 906u128, 828u128),
+        ] {
+            let data: PoolDataResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    vault.clone(),
+                    &VaultQueryMsg::QueryPoolData {
+                        pool_address: pool.to_string(),
+                    },
+                )
+                .unwrap();
+            let (reserve_in, reserve_out) = if &data.token0 == token_in {
+                (data.reserve0, data.reserve1)
+            } else {
+                (data.reserve1, data.reserve0)
+            };
+            assert_eq!(reserve_in, Uint128::from(10_000 + amount_in));
+            assert_eq!(reserve_out, Uint128::from(10_000 - amount_out));
+        }
+
+        // cw20 tokens go through a path with a cw20 hook
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdt.clone(),
+                &cw20_base::msg::ExecuteMsg::Send {
+                    contract: vault.to_string(),
+                    amount: Uint128::from(828u128),
+                    msg: to_binary(&VaultHookMsg::MultiHopSwap(multi_hop_swap(
+                        &usdt_asset,
+                        vec![(&usdc_usdt, &usdc_asset), (&osmo_usdc, &osmo)],
+                        1,
+                    )))
+                    .unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        // every hop must swap the output of the previous one
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::MultiHopSwap(multi_hop_swap(
+                    &osmo,
+                    vec![(&osmo_usdc, &usdc_asset), (&osmo_usdc, &usdt_asset)],
+                    0,
+                )),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::AssetMismatch {}.to_string()
+        );
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::MultiHopSwap(multi_hop_swap(&osmo, vec![], 0)),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::InvalidPath {
+                val: String::from("expected 1 to 4 hops, got 0"),
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn exact_output_swaps_refund_the_unused_input() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(10_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(10_000u128),
+                    amount_b_desired: Uint128::from(10_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline,
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        let swap_exact_out =
+            |token_in: &AssetInfo, token_out: &AssetInfo, amount_out: u128, amount_in_max: u128| {
+                SwapExactOutParams {
+                    pool_address: pool.to_string(),
+                    token_in: token_in.clone(),
+                    token_out: token_out.clone(),
+                    amount_out: Uint128::from(amount_out),
+                    amount_in_max: Uint128::from(amount_in_max),
+                    address_to: user.to_string(),
+                    deadline,
+                }
+            };
+        let balances = |suite: &super::helpers::Suite| -> (Uint128, Uint128) {
+            let usdc_balance: cw20::BalanceResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    usdc.clone(),
+                    &cw20_base::msg::QueryMsg::Balance {
+                        address: user.to_string(),
+                    },
+                )
+                .unwrap();
+            let osmo_balance = suite
+                .app
+                .wrap()
+                .query_balance(&user, NATIVE_DENOM)
+                .unwrap()
+                .amount;
+            (osmo_balance, usdc_balance.balance)
+        };
+
+        // 906 usdc out of a 10_000 / 10_000 pool with a 0.3% fee need 1_000 uosmo
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::SwapExactOut(swap_exact_out(&osmo, &usdc_asset, 906, 999)),
+                &coins(1_500, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::ExcessiveInputAmount {
+                amount_in: Uint128::from(1_000u128),
+                amount_in_max: Uint128::from(999u128),
+            }
+            .to_string()
+        );
+
+        let (osmo_before, usdc_before) = balances(&suite);
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::SwapExactOut(swap_exact_out(&osmo, &usdc_asset, 906, 1_500)),
+                &coins(1_500, NATIVE_DENOM),
+            )
+            .unwrap();
+        assert_eq!(
+            balances(&suite),
+            (
+                osmo_before - Uint128::from(1_000u128),
+                usdc_before + Uint128::from(906u128)
+            )
+        );
+
+        // cw20 tokens sent with the hook, 500 uosmo out of the 11_000 / 9_094 pool need 435 usdc
+        let (osmo_before, usdc_before) = balances(&suite);
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::Send {
+                    contract: vault.to_string(),
+                    amount: Uint128::from(1_000u128),
+                    msg: to_binary(&VaultHookMsg::SwapExactOut(swap_exact_out(
+                        &usdc_asset,
+                        &osmo,
+                        500,
+                        1_000,
+                    )))
+                    .unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            balances(&suite),
+            (
+                osmo_before + Uint128::from(500u128),
+                usdc_before - Uint128::from(435u128)
+            )
+        );
+    }
+
+    #[test]
+    fn simulate_and_reverse_simulate_swaps() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(10_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(10_000u128),
+                    amount_b_desired: Uint128::from(10_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline,
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        // 1_000 uosmo in a 10_000 / 10_000 pool with a 0.3% fee
+        let expected = SwapSimulationResponse {
+            amount_in: Uint128::from(1_000u128),
+            amount_out: Uint128::from(906u128),
+            fee_amount: Uint128::from(3u128),
+            spot_price_before: Decimal::one(),
+            spot_price_after: Decimal::from_ratio(9_094u128, 11_000u128),
+            price_impact: Decimal::permille(94),
+        };
+
+        let simulation: SwapSimulationResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                vault.clone(),
+                &VaultQueryMsg::SimulateSwap {
+                    pool_address: pool.to_string(),
+                    offer_asset: Asset {
+                        info: osmo.clone(),
+                        amount: Uint128::from(1_000u128),
+                    },
+                },
+            )
+            .unwrap();
+        assert_eq!(simulation, expected);
+
+        let reverse_simulation: SwapSimulationResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                vault.clone(),
+                &VaultQueryMsg::ReverseSimulateSwap {
+                    pool_address: pool.to_string(),
+                    ask_asset: Asset {
+                        info: usdc_asset.clone(),
+                        amount: Uint128::from(906u128),
+                    },
+                },
+            )
+            .unwrap();
+        assert_eq!(reverse_simulation, expected);
+
+        // the simulation matches the swap
+        let res = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::Swap(SwapTokensParams {
+                    pool_address: pool.to_string(),
+                    amount_out_min: simulation.amount_out,
+                    token_in: osmo.clone(),
+                    token_out: usdc_asset.clone(),
+                    address_to: user.to_string(),
+                    deadline,
+                    max_price_impact: None,
+                }),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        let wasm = res.events.iter().find(|event| event.ty == "wasm").unwrap();
+        assert!(wasm
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "amount_out" && attr.value == "906"));
+
+        // tokens outside of the pool can't be simulated
+        let err = suite
+            .app
+            .wrap()
+            .query_wasm_smart::<SwapSimulationResponse>(
+                vault.clone(),
+                &VaultQueryMsg::SimulateSwap {
+                    pool_address: pool.to_string(),
+                    offer_asset: Asset {
+                        info: cw20_asset(&suite.usdt),
+                        amount: Uint128::from(1_000u128),
+                    },
+                },
+            )
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&vault::ContractError::AssetMismatch {}.to_string()));
+    }
+
+    /// What the mock flash loan borrower does in its callback
+    #[cosmwasm_schema::cw_serde]
+    enum BorrowerAction {
+        Repay,
+        RepayWithoutFees,
+        CallVault,
+    }
+
+    fn borrower_execute(
+        _deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: FlashLoanReceiverMsg,
+    ) -> StdResult<Response> {
+        let FlashLoanReceiverMsg::FlashLoanCallback { assets, fees, msg } = msg;
+
+        let repaid: Vec<Asset> = match from_binary(&msg)? {
+            BorrowerAction::Repay => assets
+                .into_iter()
+                .zip(fees)
+                .map(|(loan, fee)| Asset {
+                    info: loan.asset.info,
+                    amount: loan.asset.amount + fee.amount,
+                })
+                .collect(),
+            BorrowerAction::RepayWithoutFees => assets.into_iter().map(|loan| loan.asset).collect(),
+            BorrowerAction::CallVault => {
+                return Ok(Response::new().add_message(WasmMsg::Execute {
+                    contract_addr: info.sender.to_string(),
+                    msg: to_binary(&VaultExecuteMsg::SetGuardian { guardian: None })?,
+                    funds: vec![],
+                }))
+            }
+        };
+
+        let mut messages = vec![];
+        for asset in repaid {
+            messages.push(
+                asset
+                    .info
+                    .transfer_msg(info.sender.as_str(), asset.amount)?,
+            );
+        }
+        Ok(Response::new().add_messages(messages))
+    }
+
+    fn borrower_instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn borrower_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_binary(&Empty {})
+    }
+
+    #[test]
+    fn flash_loans_are_repaid_with_fees_to_the_pools() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(10_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(10_000u128),
+                    amount_b_desired: Uint128::from(10_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline,
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        // the borrower holds some tokens to pay the fees with
+        let borrower_code = suite.app.store_code(Box::new(ContractWrapper::new(
+            borrower_execute,
+            borrower_instantiate,
+            borrower_query,
+        )));
+        let borrower = suite
+            .app
+            .instantiate_contract(
+                borrower_code,
+                user.clone(),
+                &Empty {},
+                &[],
+                "borrower",
+                None,
+            )
+            .unwrap();
+        suite
+            .app
+            .send_tokens(user.clone(), borrower.clone(), &coins(100, NATIVE_DENOM))
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::Transfer {
+                    recipient: borrower.to_string(),
+                    amount: Uint128::from(100u128),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let flash_loan = |action: BorrowerAction| VaultExecuteMsg::FlashLoan {
+            assets: vec![
+                FlashLoanAsset {
+                    pool_address: pool.to_string(),
+                    asset: Asset {
+                        info: osmo.clone(),
+                        amount: Uint128::from(1_000u128),
+                    },
+                },
+                FlashLoanAsset {
+                    pool_address: pool.to_string(),
+                    asset: Asset {
+                        info: usdc_asset.clone(),
+                        amount: Uint128::from(500u128),
+                    },
+                },
+            ],
+            recipient: borrower.to_string(),
+            msg: to_binary(&action).unwrap(),
+        };
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &flash_loan(BorrowerAction::RepayWithoutFees),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::FlashLoanNotRepaid {
+                val: format!("expected a balance of 10003{}, got 10000", NATIVE_DENOM),
+            }
+            .to_string()
+        );
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &flash_loan(BorrowerAction::CallVault),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::FlashLoanInProgress {}.to_string()
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &flash_loan(BorrowerAction::Repay),
+                &[],
+            )
+            .unwrap();
+
+        // 0.3% of 1_000 uosmo and of 500 usdc, rounded up, are credited to the pool
+        let data: PoolDataResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                vault.clone(),
+                &VaultQueryMsg::QueryPoolData {
+                    pool_address: pool.to_string(),
+                },
+            )
+            .unwrap();
+        let (osmo_reserve, usdc_reserve) = if data.token0 == osmo {
+            (data.reserve0, data.reserve1)
+        } else {
+            (data.reserve1, data.reserve0)
+        };
+        assert_eq!(osmo_reserve, Uint128::from(10_003u128));
+        assert_eq!(usdc_reserve, Uint128::from(10_002u128));
+
+        // a token can't be lent twice out of the same reserve
+        let lend_twice = VaultExecuteMsg::FlashLoan {
+            assets: vec![
+                FlashLoanAsset {
+                    pool_address: pool.to_string(),
+                    asset: Asset {
+                        info: osmo.clone(),
+                        amount: Uint128::from(10_000u128),
+                    },
+                },
+                FlashLoanAsset {
+                    pool_address: pool.to_string(),
+                    asset: Asset {
+                        info: osmo.clone(),
+                        amount: Uint128::from(1_000u128),
+                    },
+                },
+            ],
+            recipient: borrower.to_string(),
+            msg: to_binary(&BorrowerAction::Repay).unwrap(),
+        };
+        let err = suite
+            .app
+            .execute_contract(user.clone(), vault.clone(), &lend_twice, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::DuplicateFlashLoanAsset {
+                pool_address: pool.to_string(),
+                token: osmo.to_string(),
+            }
+            .to_string()
+        );
+
+        // funds can't be attached to a loan
+        let lend = VaultExecuteMsg::FlashLoan {
+            assets: vec![FlashLoanAsset {
+                pool_address: pool.to_string(),
+                asset: Asset {
+                    info: osmo.clone(),
+                    amount: Uint128::from(1_000u128),
+                },
+            }],
+            recipient: borrower.to_string(),
+            msg: to_binary(&BorrowerAction::Repay).unwrap(),
+        };
+        let err = suite
+            .app
+            .execute_contract(user.clone(), vault.clone(), &lend, &coins(10, NATIVE_DENOM))
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::FlashLoanNonPayable {}.to_string()
+        );
+
+        // the vault can be used again once the loan is settled
+        suite
+            .app
+            .execute_contract(
+                suite.app.api().addr_make("vault_owner"),
+                vault.clone(),
+                &VaultExecuteMsg::SetGuardian { guardian: None },
+                &[],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn protocol_fee_switch_mints_lp_growth_to_fee_to() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let vault_owner = suite.app.api().addr_make("vault_owner");
+        let fee_to = suite.app.api().addr_make("fee_to");
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        // only the owner turns the fee on
+        let set_fee_to = VaultExecuteMsg::SetFeeTo {
+            fee_to: Some(fee_to.to_string()),
+        };
+        let err = suite
+            .app
+            .execute_contract(user.clone(), vault.clone(), &set_fee_to, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::Unauthorized {}.to_string()
+        );
+        suite
+            .app
+            .execute_contract(vault_owner.clone(), vault.clone(), &set_fee_to, &[])
+            .unwrap();
+
+        let fee_to_response: FeeToResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(vault.clone(), &VaultQueryMsg::FeeTo {})
+            .unwrap();
+        assert_eq!(fee_to_response.fee_to, Some(fee_to.to_string()));
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(300_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let add_liquidity = |amount: u128| {
+            VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                pool_address: pool.to_string(),
+                token_a: osmo.clone(),
+                token_b: usdc_asset.clone(),
+                amount_a_desired: Uint128::from(amount),
+                amount_b_desired: Uint128::from(amount),
+                amount_a_min: Uint128::zero(),
+                amount_b_min: Uint128::zero(),
+                address_to: user.to_string(),
+                deadline,
+                on_receive: None,
+                min_liquidity: None,
+            })
+        };
+        let swap = VaultExecuteMsg::Swap(SwapTokensParams {
+            pool_address: pool.to_string(),
+            amount_out_min: Uint128::zero(),
+            token_in: osmo.clone(),
+            token_out: usdc_asset.clone(),
+            address_to: user.to_string(),
+            deadline,
+            max_price_impact: None,
+        });
+        let fee_to_balance = |suite: &super::helpers::Suite| -> Uint128 {
+            let res: cw20::BalanceResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    pool.clone(),
+                    &PoolQueryMsg::Balance {
+                        address: fee_to.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance
+        };
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity(100_000),
+                &coins(100_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        assert_eq!(fee_to_balance(&suite), Uint128::zero());
+
+        // the swap grows sqrt(k) from 100_000 to 100_050, 1/6th of that growth goes to fee_to
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &swap,
+                &coins(50_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity(10_000),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        assert_eq!(fee_to_balance(&suite), Uint128::from(8u128));
+
+        // once turned off, the growth stays with the liquidity providers
+        suite
+            .app
+            .execute_contract(
+                vault_owner.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::SetFeeTo { fee_to: None },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &swap,
+                &coins(50_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &add_liquidity(10_000),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        assert_eq!(fee_to_balance(&suite), Uint128::from(8u128));
+    }
+
+    #[test]
+    fn sync_skim_and_solvency_against_vault_balances() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let vault_owner = suite.app.api().addr_make("vault_owner");
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(10_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(10_000u128),
+                    amount_b_desired: Uint128::from(10_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline,
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        // tokens transferred to the vault directly aren't part of any reserve
+        let donate = |suite: &mut super::helpers::Suite, amount_osmo: u128, amount_usdc: u128| {
+            suite
+                .app
+                .send_tokens(
+                    user.clone(),
+                    vault.clone(),
+                    &coins(amount_osmo, NATIVE_DENOM),
+                )
+                .unwrap();
+            suite
+                .app
+                .execute_contract(
+                    user.clone(),
+                    usdc.clone(),
+                    &cw20_base::msg::ExecuteMsg::Transfer {
+                        recipient: vault.to_string(),
+                        amount: Uint128::from(amount_usdc),
+                    },
+                    &[],
+                )
+                .unwrap();
+        };
+        let solvency = |suite: &super::helpers::Suite| -> SolvencyResponse {
+            suite
+                .app
+                .wrap()
+                .query_wasm_smart(vault.clone(), &VaultQueryMsg::Solvency {})
+                .unwrap()
+        };
+        let token_solvency = |response: &SolvencyResponse, token: &AssetInfo| {
+            let token = response
+                .tokens
+                .iter()
+                .find(|solvency| &solvency.token == token)
+                .unwrap();
+            (token.reserves.u128(), token.balance.u128())
+        };
+
+        donate(&mut suite, 500, 300);
+        let response = solvency(&suite);
+        assert!(response.solvent);
+        assert_eq!(token_solvency(&response, &osmo), (10_000, 10_500));
+        assert_eq!(token_solvency(&response, &usdc_asset), (10_000, 10_300));
+
+        // only the owner reconciles the reserves
+        let skim = VaultExecuteMsg::Skim {
+            pool_address: pool.to_string(),
+            to: vault_owner.to_string(),
+        };
+        let err = suite
+            .app
+            .execute_contract(user.clone(), vault.clone(), &skim, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::Unauthorized {}.to_string()
+        );
+
+        suite
+            .app
+            .execute_contract(vault_owner.clone(), vault.clone(), &skim, &[])
+            .unwrap();
+        assert_eq!(
+            suite
+                .app
+                .wrap()
+                .query_balance(&vault_owner, NATIVE_DENOM)
+                .unwrap()
+                .amount,
+            Uint128::from(500u128)
+        );
+        let response = solvency(&suite);
+        assert_eq!(token_solvency(&response, &osmo), (10_000, 10_000));
+        assert_eq!(token_solvency(&response, &usdc_asset), (10_000, 10_000));
+
+        // sync adds the excess to the reserves of the pool
+        donate(&mut suite, 200, 100);
+        suite
+            .app
+            .execute_contract(
+                vault_owner.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::Sync {
+                    pool_address: pool.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        let response = solvency(&suite);
+        assert_eq!(token_solvency(&response, &osmo), (10_200, 10_200));
+        assert_eq!(token_solvency(&response, &usdc_asset), (10_100, 10_100));
+    }
+
+    #[test]
+    fn twap_accumulates_prices_between_reserve_changes() {
+        let mut suite = setup();
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(10_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(10_000u128),
+                    amount_b_desired: Uint128::from(10_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline: deadline(&suite.app),
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        let deposited_at = suite.app.block_info().time.seconds();
+
+        // the 1:1 price holds for 100 seconds before a swap moves it
+        suite.app.update_block(|block| {
+            block.time = block.time.plus_seconds(100);
+            block.height += 20;
+        });
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::Swap(SwapTokensParams {
+                    pool_address: pool.to_string(),
+                    amount_out_min: Uint128::zero(),
+                    token_in: osmo.clone(),
+                    token_out: usdc_asset.clone(),
+                    address_to: user.to_string(),
+                    deadline: deadline(&suite.app),
+                    max_price_impact: None,
+                }),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        let swapped_at = suite.app.block_info().time.seconds();
+        suite.app.update_block(|block| {
+            block.time = block.time.plus_seconds(100);
+            block.height += 20;
+        });
+        let now = suite.app.block_info().time.seconds();
+
+        let pool_data: PoolDataResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                vault.clone(),
+                &VaultQueryMsg::QueryPoolData {
+                    pool_address: pool.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(pool_data.block_time_last, swapped_at);
+        assert_eq!(
+            pool_data.price0_cumulative_last,
+            Decimal256::from_ratio(100u128, 1u128)
+        );
+
+        let twap = |app: &TestApp, start_time: u64, end_time: u64| {
+            app.wrap().query_wasm_smart::<TwapResponse>(
+                vault.clone(),
+                &VaultQueryMsg::Twap {
+                    pool_address: pool.to_string(),
+                    start_time,
+                    end_time,
+                },
+            )
+        };
+
+        // 11_000 uosmo and 9_094 usdc after the swap
+        let (osmo_price, usdc_price) = (
+            Decimal256::from_ratio(9_094u128, 11_000u128),
+            Decimal256::from_ratio(11_000u128, 9_094u128),
+        );
+        let (price0, price1) = if pool_data.token0 == osmo {
+            (osmo_price, usdc_price)
+        } else {
+            (usdc_price, osmo_price)
+        };
+
+        let before_swap = twap(&suite.app, deposited_at, swapped_at).unwrap();
+        assert_eq!(before_swap.price0_average, Decimal256::one());
+        assert_eq!(before_swap.price1_average, Decimal256::one());
+
+        let after_swap = twap(&suite.app, swapped_at, now).unwrap();
+        assert_eq!(after_swap.price0_average, price0);
+        assert_eq!(after_swap.price1_average, price1);
+
+        // half of the window at each price, within the rounding of the accumulators
+        let whole_window = twap(&suite.app, deposited_at + 50, swapped_at + 50).unwrap();
+        let expected = (Decimal256::one() + price0) / Decimal256::from_ratio(2u128, 1u128);
+        assert!(whole_window.price0_average.abs_diff(expected) < Decimal256::permille(1));
+
+        // no observation before the deposit, and no price from the future
+        assert!(twap(&suite.app, deposited_at - 1, now).is_err());
+        assert!(twap(&suite.app, swapped_at, now + 1).is_err());
+        assert!(twap(&suite.app, now, now).is_err());
+
+        // a reserve change a whole window later prunes the observations before it, except the last one
+        let observation = |app: &TestApp, observed_at: u64| {
+            let pool_address = pool.to_string();
+            let mut key = Vec::new();
+            for part in ["price_observations".as_bytes(), pool_address.as_bytes()] {
+                key.extend_from_slice(&(part.len() as u16).to_be_bytes());
+                key.extend_from_slice(part);
+            }
+            key.extend_from_slice(&observed_at.to_be_bytes());
+            app.wrap().query_wasm_raw(vault.clone(), key).unwrap()
+        };
+        assert!(observation(&suite.app, deposited_at).is_some());
+
+        suite.app.update_block(|block| {
+            block.time = block.time.plus_seconds(MAX_TWAP_WINDOW);
+            block.height += 100_000;
+        });
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::Swap(SwapTokensParams {
+                    pool_address: pool.to_string(),
+                    amount_out_min: Uint128::zero(),
+                    token_in: osmo.clone(),
+                    token_out: usdc_asset.clone(),
+                    address_to: user.to_string(),
+                    deadline: deadline(&suite.app),
+                    max_price_impact: None,
+                }),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        let later = suite.app.block_info().time.seconds();
+
+        assert!(observation(&suite.app, deposited_at).is_none());
+        assert!(observation(&suite.app, swapped_at).is_some());
+        assert!(twap(&suite.app, now - 1, later).is_err());
+
+        // the window starts from the kept observation
+        let whole_window = twap(&suite.app, now, later).unwrap();
+        assert_eq!(whole_window.price0_average, price0);
+    }
+
+    #[test]
+    fn pools_are_listed_and_indexed_by_token() {
+        let mut suite = setup();
+        let (osmo, usdc, usdt) = (
+            native_asset(NATIVE_DENOM),
+            cw20_asset(&suite.usdc),
+            cw20_asset(&suite.usdt),
+        );
+
+        let mut pools = [
+            suite.create_pool_with(
+                osmo.clone(),
+                usdc.clone(),
+                PoolType::ConstantProduct,
+                30,
+                None,
+            ),
+            suite.create_pool_with(
+                usdc.clone(),
+                usdt.clone(),
+                PoolType::ConstantProduct,
+                30,
+                None,
+            ),
+            suite.create_pool_with(
+                osmo.clone(),
+                usdt.clone(),
+                PoolType::ConstantProduct,
+                30,
+                None,
+            ),
+        ];
+        pools.sort();
+        let vault = suite.vault.clone();
+
+        let addresses = |response: PoolsResponse| {
+            response
+                .pools
+                .into_iter()
+                .map(|pool| pool.pool_address)
+                .collect::<Vec<_>>()
+        };
+
+        let all: PoolsResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                vault.clone(),
+                &VaultQueryMsg::AllPools {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            addresses(all),
+            pools.iter().map(Addr::to_string).collect::<Vec<_>>()
+        );
+
+        let page: PoolsResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                vault.clone(),
+                &VaultQueryMsg::AllPools {
+                    start_after: Some(pools[0].to_string()),
+                    limit: Some(1),
+                },
+            )
+            .unwrap();
+        assert_eq!(addresses(page), vec![pools[1].to_string()]);
+
+        // usdc is token0 of a pool and token1 of another one, depending on the pair order
+        let by_token = |token: &AssetInfo, start_after: Option<String>| -> Vec<String> {
+            addresses(
+                suite
+                    .app
+                    .wrap()
+                    .query_wasm_smart(
+                        vault.clone(),
+                        &VaultQueryMsg::PoolsByToken {
+                            token: token.clone(),
+                            start_after,
+                            limit: None,
+                        },
+                    )
+                    .unwrap(),
+            )
+        };
+        for token in [&osmo, &usdc, &usdt] {
+            let all: PoolsResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    vault.clone(),
+                    &VaultQueryMsg::AllPools {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            let expected = all
+                .pools
+                .into_iter()
+                .filter(|pool| &pool.pool_data.token0 == token || &pool.pool_data.token1 == token)
+                .map(|pool| pool.pool_address)
+                .collect::<Vec<_>>();
+            assert_eq!(expected.len(), 2);
+            assert_eq!(by_token(token, None), expected);
+            assert_eq!(
+                by_token(token, Some(expected[0].clone())),
+                vec![expected[1].clone()]
+            );
+        }
+
+        // the registered factories are listed a page at a time
+        let other_factory = suite.app.api().addr_make("other_factory");
+        suite
+            .app
+            .execute_contract(
+                suite.app.api().addr_make("vault_owner"),
+                vault.clone(),
+                &VaultExecuteMsg::RegisterFactory {
+                    factory_address: other_factory.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        let config = |start_after: Option<String>, limit: Option<u32>| -> ConfigResponse {
+            suite
+                .app
+                .wrap()
+                .query_wasm_smart(vault.clone(), &VaultQueryMsg::Config { start_after, limit })
+                .unwrap()
+        };
+        let mut factories = vec![suite.factory.to_string(), other_factory.to_string()];
+        factories.sort();
+        assert_eq!(
+            config(None, None),
+            ConfigResponse {
+                owner: suite.app.api().addr_make("vault_owner").to_string(),
+                factories: factories.clone(),
+            }
+        );
+        assert_eq!(config(None, Some(1)).factories, vec![factories[0].clone()]);
+        assert_eq!(
+            config(Some(factories[0].clone()), None).factories,
+            vec![factories[1].clone()]
+        );
+
+        for (factory_address, registered) in [(&suite.factory, true), (&suite.user, false)] {
+            let response: FactoryRegisteredResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    vault.clone(),
+                    &VaultQueryMsg::FactoryRegistered {
+                        factory_address: factory_address.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(response.registered, registered);
+        }
+    }
+
+    /// The mock LP token receiver, a staking contract taking the LP tokens with cw20 `Send`
+    #[cosmwasm_schema::cw_serde]
+    enum ReceiverExecuteMsg {
+        Receive(Cw20ReceiveMsg),
+    }
+
+    fn receiver_execute(
+        _deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: ReceiverExecuteMsg,
+    ) -> StdResult<Response> {
+        let ReceiverExecuteMsg::Receive(receive) = msg;
+
+        Ok(Response::new()
+            .add_attribute("lp_token", info.sender)
+            .add_attribute("sender", receive.sender)
+            .add_attribute("amount", receive.amount)
+            .add_attribute("msg", receive.msg.to_base64()))
+    }
+
+    #[test]
+    fn lp_tokens_are_minted_to_address_to_and_sent_to_hooks() {
+        let mut suite = setup();
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+        let recipient = suite.app.api().addr_make("lp_recipient");
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        let receiver_code = suite.app.store_code(Box::new(ContractWrapper::new(
+            receiver_execute,
+            borrower_instantiate,
+            borrower_query,
+        )));
+        let receiver = suite
+            .app
+            .instantiate_contract(
+                receiver_code,
+                user.clone(),
+                &Empty {},
+                &[],
+                "lp receiver",
+                None,
+            )
+            .unwrap();
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(11_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let deadline = deadline(&suite.app);
+        let add_liquidity = |amount: u128, address_to: &Addr, on_receive: Option<Binary>| {
+            VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                pool_address: pool.to_string(),
+                token_a: osmo.clone(),
+                token_b: usdc_asset.clone(),
+                amount_a_desired: Uint128::from(amount),
+                amount_b_desired: Uint128::from(amount),
+                amount_a_min: Uint128::zero(),
+                amount_b_min: Uint128::zero(),
+                address_to: address_to.to_string(),
+                deadline,
+                on_receive,
+                min_liquidity: None,
+            })
+        };
+        let lp_balance = |app: &TestApp, address: &Addr| {
+            let res: cw20::BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    pool.clone(),
+                    &PoolQueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance
+        };
+
+        // the LP tokens go to `address_to`, not to the depositor
+        let msg = add_liquidity(10_000, &recipient, None);
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &msg,
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        assert!(!lp_balance(&suite.app, &recipient).is_zero());
+        assert!(lp_balance(&suite.app, &user).is_zero());
+
+        // with a hook they are delivered to the receiver contract with cw20 `Send`
+        let hook = to_binary(&"stake").unwrap();
+        let msg = add_liquidity(1_000, &receiver, Some(hook.clone()));
+        let res = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &msg,
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        let received = res
+            .events
+            .iter()
+            .find(|event| {
+                event.ty == "wasm"
+                    && event.attributes.iter().any(|attr| {
+                        attr.key == "_contract_address" && attr.value == receiver.as_str()
+                    })
+            })
+            .expect("the receiver hook was not called");
+        let attribute = |key: &str| {
+            received
+                .attributes
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.clone())
+                .unwrap()
+        };
+        assert_eq!(attribute("lp_token"), pool.to_string());
+        assert_eq!(attribute("sender"), pool.to_string());
+        assert_eq!(attribute("msg"), hook.to_base64());
+
+        let amount: Uint128 = attribute("amount").parse().unwrap();
+        assert!(!amount.is_zero());
+        assert_eq!(lp_balance(&suite.app, &receiver), amount);
+        assert!(lp_balance(&suite.app, &user).is_zero());
+
+        // a hook to an address that isn't a contract fails the deposit
+        let msg = add_liquidity(1_000, &recipient, Some(hook));
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &msg,
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+    }
+
+    #[test]
+    fn pending_deposits_provide_liquidity_without_allowances() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault) = (suite.user.clone(), suite.vault.clone());
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+        let vault_owner = suite.app.api().addr_make("vault_owner");
+        let (osmo, usdc_asset, usdt_asset) = (
+            native_asset(NATIVE_DENOM),
+            cw20_asset(&usdc),
+            cw20_asset(&usdt),
+        );
+
+        let pool = suite.create_pool_with(
+            usdc_asset.clone(),
+            usdt_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+        let osmo_pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        let send =
+            |suite: &mut super::helpers::Suite, token: &Addr, amount: u128, hook: VaultHookMsg| {
+                suite.app.execute_contract(
+                    user.clone(),
+                    token.clone(),
+                    &cw20_base::msg::ExecuteMsg::Send {
+                        contract: vault.to_string(),
+                        amount: Uint128::from(amount),
+                        msg: to_binary(&hook).unwrap(),
+                    },
+                    &[],
+                )
+            };
+        let deposit_pending = |pool: &Addr| VaultHookMsg::DepositPending {
+            pool_address: pool.to_string(),
+        };
+        let provide_pending = |pool: &Addr| ProvidePendingParams {
+            pool_address: pool.to_string(),
+            amount0_min: Uint128::zero(),
+            amount1_min: Uint128::zero(),
+            address_to: user.to_string(),
+            deadline,
+            on_receive: None,
+        };
+        let pending = |suite: &super::helpers::Suite| -> Vec<PendingDeposit> {
+            let res: PendingDepositsResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    vault.clone(),
+                    &VaultQueryMsg::PendingDeposits {
+                        depositor: user.to_string(),
+                        pool_address: None,
+                    },
+                )
+                .unwrap();
+            res.deposits
+        };
+        let balance = |suite: &super::helpers::Suite, token: &Addr, address: &Addr| -> u128 {
+            let res: cw20::BalanceResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    token.clone(),
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance.u128()
+        };
+        let reserves = |suite: &super::helpers::Suite, pool: &Addr| -> (u128, u128) {
+            let res: PoolDataResponse = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    vault.clone(),
+                    &VaultQueryMsg::QueryPoolData {
+                        pool_address: pool.to_string(),
+                    },
+                )
+                .unwrap();
+            (res.reserve0.u128(), res.reserve1.u128())
+        };
+
+        // the escrowed tokens are neither reserves nor skimmable
+        send(&mut suite, &usdc, 10_000, deposit_pending(&pool)).unwrap();
+        assert_eq!(
+            pending(&suite),
+            vec![PendingDeposit {
+                pool_address: pool.to_string(),
+                assets: vec![Asset {
+                    info: usdc_asset.clone(),
+                    amount: Uint128::from(10_000u128),
+                }],
+            }]
+        );
+
+        let solvency: SolvencyResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(vault.clone(), &VaultQueryMsg::Solvency {})
+            .unwrap();
+        assert!(solvency.solvent);
+        assert!(solvency.tokens.contains(&TokenSolvency {
+            token: usdc_asset.clone(),
+            reserves: Uint128::zero(),
+            balance: Uint128::from(10_000u128),
+            pending: Uint128::from(10_000u128),
+        }));
+
+        suite
+            .app
+            .execute_contract(
+                vault_owner.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::Skim {
+                    pool_address: pool.to_string(),
+                    to: vault_owner.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(balance(&suite, &usdc, &vault_owner), 0);
+
+        // the escape hatch sends the escrow back
+        let withdraw = VaultExecuteMsg::WithdrawPending {
+            pool_address: pool.to_string(),
+        };
+        suite
+            .app
+            .execute_contract(user.clone(), vault.clone(), &withdraw, &[])
+            .unwrap();
+        assert_eq!(balance(&suite, &usdc, &user), 1_000_000);
+        assert!(pending(&suite).is_empty());
+
+        let err = suite
+            .app
+            .execute_contract(user.clone(), vault.clone(), &withdraw, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::NoPendingDeposit {
+                pool_address: pool.to_string(),
+            }
+            .to_string()
+        );
+
+        // the second `Send` provides the liquidity
+        send(&mut suite, &usdc, 10_000, deposit_pending(&pool)).unwrap();
+        send(
+            &mut suite,
+            &usdt,
+            10_000,
+            VaultHookMsg::ProvidePending(provide_pending(&pool)),
+        )
+        .unwrap();
+        assert_eq!(reserves(&suite, &pool), (10_000, 10_000));
+        assert!(balance(&suite, &pool, &user) > 0);
+        assert!(pending(&suite).is_empty());
+
+        // what doesn't match the price of the pool is refunded
+        send(&mut suite, &usdc, 1_000, deposit_pending(&pool)).unwrap();
+        send(
+            &mut suite,
+            &usdt,
+            2_000,
+            VaultHookMsg::ProvidePending(provide_pending(&pool)),
+        )
+        .unwrap();
+        assert_eq!(reserves(&suite, &pool), (11_000, 11_000));
+        assert_eq!(balance(&suite, &usdc, &user), 989_000);
+        assert_eq!(balance(&suite, &usdt, &user), 989_000);
+
+        // a single token isn't enough
+        send(&mut suite, &usdc, 2_000, deposit_pending(&osmo_pool)).unwrap();
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::ProvidePending(provide_pending(&osmo_pool)),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::InsufficientAmount {}.to_string()
+        );
+
+        // native tokens attached to `ProvidePending` join the escrow
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::ProvidePending(provide_pending(&osmo_pool)),
+                &coins(2_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        assert_eq!(reserves(&suite, &osmo_pool), (2_000, 2_000));
+        assert!(pending(&suite).is_empty());
+
+        // the escrow totals are back to zero once every deposit left it
+        let solvency: SolvencyResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(vault.clone(), &VaultQueryMsg::Solvency {})
+            .unwrap();
+        assert!(solvency.solvent);
+        assert!(solvency.tokens.iter().all(|token| token.pending.is_zero()));
+    }
+
+    #[test]
+    fn single_sided_deposits_swap_the_optimal_part_first() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(10_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(10_000u128),
+                    amount_b_desired: Uint128::from(10_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline,
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        let provide_single_sided =
+            |token_in: &AssetInfo, min_liquidity: u128| ProvideSingleSidedParams {
+                pool_address: pool.to_string(),
+                token_in: token_in.clone(),
+                min_liquidity: Uint128::from(min_liquidity),
+                address_to: user.to_string(),
+                deadline,
+                on_receive: None,
+            };
+        let lp_balance = |app: &TestApp| {
+            let res: cw20::BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    pool.clone(),
+                    &PoolQueryMsg::Balance {
+                        address: user.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance
+        };
+        let reserves = |app: &TestApp| {
+            let res: PoolDataResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    vault.clone(),
+                    &VaultQueryMsg::QueryPoolData {
+                        pool_address: pool.to_string(),
+                    },
+                )
+                .unwrap();
+            if res.token0 == osmo {
+                (res.reserve0, res.reserve1)
+            } else {
+                (res.reserve1, res.reserve0)
+            }
+        };
+
+        // 488 of the 1_000 uosmo are swapped for 463 usdc, 509 uosmo match them and 3 are refunded
+        assert_eq!(
+            vault::contract::zap_swap_amount(
+                Uint128::from(1_000u128),
+                Uint128::from(10_000u128),
+                30
+            )
+            .unwrap(),
+            Uint128::from(488u128)
+        );
+        let lp_before = lp_balance(&suite.app);
+        let osmo_before = suite
+            .app
+            .wrap()
+            .query_balance(&user, NATIVE_DENOM)
+            .unwrap()
+            .amount;
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::ProvideSingleSided(provide_single_sided(&osmo, 1)),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        let osmo_after = suite
+            .app
+            .wrap()
+            .query_balance(&user, NATIVE_DENOM)
+            .unwrap()
+            .amount;
+        assert_eq!(osmo_before - osmo_after, Uint128::from(997u128));
+        assert_eq!(
+            reserves(&suite.app),
+            (Uint128::from(10_997u128), Uint128::from(10_000u128))
+        );
+        assert!(lp_balance(&suite.app) > lp_before);
+
+        // the deposit fails when too few LP tokens are minted
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::ProvideSingleSided(provide_single_sided(&osmo, 1_000_000)),
+                &coins(1_000, NATIVE_DENOM),
+            )
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("is below the minimum"));
+
+        // cw20 tokens go through the hook, the sent token must be `token_in`
+        let send = |token_in: &AssetInfo| cw20_base::msg::ExecuteMsg::Send {
+            contract: vault.to_string(),
+            amount: Uint128::from(1_000u128),
+            msg: to_binary(&VaultHookMsg::ProvideSingleSided(provide_single_sided(
+                token_in, 1,
+            )))
+            .unwrap(),
+        };
+        let err = suite
+            .app
+            .execute_contract(user.clone(), usdc.clone(), &send(&osmo), &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::AssetMismatch {}.to_string()
+        );
+
+        let lp_before = lp_balance(&suite.app);
+        suite
+            .app
+            .execute_contract(user.clone(), usdc.clone(), &send(&usdc_asset), &[])
+            .unwrap();
+        assert!(lp_balance(&suite.app) > lp_before);
+
+        let solvency: SolvencyResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(vault.clone(), &VaultQueryMsg::Solvency {})
+            .unwrap();
+        assert!(solvency.solvent);
+    }
+
+    #[test]
+    fn zap_out_withdraws_into_a_single_token() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(10_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(10_000u128),
+                    amount_b_desired: Uint128::from(10_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline,
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        let withdraw = |token_out: &AssetInfo, min_out: u128| cw20_base::msg::ExecuteMsg::Send {
+            contract: pool.to_string(),
+            amount: Uint128::from(1_000u128),
+            msg: to_binary(&packages::pool_msg::RemoveLiquidityPoolParams {
+                vault_contract_addresss: vault.to_string(),
+                amount_a_min: Uint128::zero(),
+                amount_b_min: Uint128::zero(),
+                address_to: user.to_string(),
+                deadline,
+                single_token_out: Some(SingleTokenOut {
+                    token_out: token_out.clone(),
+                    min_out: Uint128::from(min_out),
+                }),
+            })
+            .unwrap(),
+        };
+        let usdc_balance = |app: &TestApp| {
+            let res: cw20::BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    usdc.clone(),
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: user.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance
+        };
+
+        // the share of each token, and the usdc one swapped for uosmo with the reserves left
+        let token_info: cw20::TokenInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(pool.clone(), &PoolQueryMsg::TokenInfo {})
+            .unwrap();
+        let share = Uint128::from(1_000u128).multiply_ratio(10_000u128, token_info.total_supply);
+        let reserve_left = Uint128::from(10_000u128) - share;
+        let swapped = (share * Uint128::from(9_970u128) * reserve_left)
+            / (reserve_left * Uint128::from(10_000u128) + share * Uint128::from(9_970u128));
+        let expected = share + swapped;
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                pool.clone(),
+                &withdraw(&osmo, expected.u128() + 1),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::SlippageExceeded {
+                amount_out: expected,
+                amount_out_min: expected + Uint128::one(),
+            }
+            .to_string()
+        );
+
+        let usdc_before = usdc_balance(&suite.app);
+        let osmo_before = suite
+            .app
+            .wrap()
+            .query_balance(&user, NATIVE_DENOM)
+            .unwrap()
+            .amount;
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                pool.clone(),
+                &withdraw(&osmo, expected.u128()),
+                &[],
+            )
+            .unwrap();
+        let osmo_after = suite
+            .app
+            .wrap()
+            .query_balance(&user, NATIVE_DENOM)
+            .unwrap()
+            .amount;
+        assert_eq!(osmo_after - osmo_before, expected);
+        assert_eq!(usdc_balance(&suite.app), usdc_before);
+
+        // the reserves still match the balances of the vault
+        let solvency: SolvencyResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(vault.clone(), &VaultQueryMsg::Solvency {})
+            .unwrap();
+        assert!(solvency.solvent);
+        assert!(solvency
+            .tokens
+            .iter()
+            .all(|token| token.balance == token.reserves));
+
+        // the token out must be one of the pool
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                pool.clone(),
+                &withdraw(&cw20_asset(&suite.usdt), 0),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::AssetMismatch {}.to_string()
+        );
+    }
+
+    #[test]
+    fn lp_tokens_sent_to_the_vault_are_withdrawn_at_its_reserves() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+        let vault_owner = suite.app.api().addr_make("vault_owner");
+        let fee_to = suite.app.api().addr_make("fee_to");
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+        suite
+            .app
+            .execute_contract(
+                vault_owner,
+                vault.clone(),
+                &VaultExecuteMsg::SetFeeTo {
+                    fee_to: Some(fee_to.to_string()),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(100_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(100_000u128),
+                    amount_b_desired: Uint128::from(100_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline,
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(100_000, NATIVE_DENOM),
+            )
+            .unwrap();
+        // the swap earns the fees the protocol fee is minted from at the next liquidity event
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::Swap(SwapTokensParams {
+                    pool_address: pool.to_string(),
+                    amount_out_min: Uint128::zero(),
+                    token_in: osmo.clone(),
+                    token_out: usdc_asset.clone(),
+                    address_to: user.to_string(),
+                    deadline,
+                    max_price_impact: None,
+                }),
+                &coins(50_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        let withdraw = |amount0_min: Uint128| cw20_base::msg::ExecuteMsg::Send {
+            contract: vault.to_string(),
+            amount: Uint128::from(10_000u128),
+            msg: to_binary(&VaultHookMsg::WithdrawLiquidity(WithdrawLiquidityParams {
+                amount0_min,
+                amount1_min: Uint128::zero(),
+                address_to: user.to_string(),
+                deadline,
+                single_token_out: None,
+            }))
+            .unwrap(),
+        };
+        let lp_balance = |app: &TestApp, address: &Addr| {
+            let res: cw20::BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    pool.clone(),
+                    &PoolQueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance
+        };
+        let pool_data = |app: &TestApp| -> PoolDataResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    vault.clone(),
+                    &VaultQueryMsg::QueryPoolData {
+                        pool_address: pool.to_string(),
+                    },
+                )
+                .unwrap()
+        };
+        let balances = |app: &TestApp| {
+            let osmo_balance = app
+                .wrap()
+                .query_balance(&user, NATIVE_DENOM)
+                .unwrap()
+                .amount;
+            let res: cw20::BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    usdc.clone(),
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: user.to_string(),
+                    },
+                )
+                .unwrap();
+            (osmo_balance, res.balance)
+        };
+
+        // only the vault of the pool's factory can burn through the pool
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                pool.clone(),
+                &PoolExecuteMsg::BurnFromVault {
+                    amount: Uint128::from(10_000u128),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            uniswapv2_pool::ContractError::Unauthorized {}.to_string()
+        );
+
+        let before = pool_data(&suite.app);
+        let (osmo_before, usdc_before) = balances(&suite.app);
+        let user_lp = lp_balance(&suite.app, &user);
+        suite
+            .app
+            .execute_contract(user.clone(), pool.clone(), &withdraw(Uint128::zero()), &[])
+            .unwrap();
+
+        // the protocol fee is minted before the burn, the share is of the supply including it
+        let fee_to_lp = lp_balance(&suite.app, &fee_to);
+        assert!(!fee_to_lp.is_zero());
+        assert_eq!(
+            lp_balance(&suite.app, &user),
+            user_lp - Uint128::from(10_000u128)
+        );
+        assert!(lp_balance(&suite.app, &vault).is_zero());
+
+        let token_info: cw20::TokenInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(pool.clone(), &PoolQueryMsg::TokenInfo {})
+            .unwrap();
+        let supply = token_info.total_supply + Uint128::from(10_000u128);
+        let amount0 = Uint128::from(10_000u128).multiply_ratio(before.reserve0, supply);
+        let amount1 = Uint128::from(10_000u128).multiply_ratio(before.reserve1, supply);
+
+        let after = pool_data(&suite.app);
+        assert_eq!(after.reserve0, before.reserve0 - amount0);
+        assert_eq!(after.reserve1, before.reserve1 - amount1);
+
+        let (osmo_after, usdc_after) = balances(&suite.app);
+        let (osmo_out, usdc_out) = if before.token0 == osmo {
+            (amount0, amount1)
+        } else {
+            (amount1, amount0)
+        };
+        assert_eq!(osmo_after - osmo_before, osmo_out);
+        assert_eq!(usdc_after - usdc_before, usdc_out);
+
+        let solvency: SolvencyResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(vault.clone(), &VaultQueryMsg::Solvency {})
+            .unwrap();
+        assert!(solvency.solvent);
+
+        // no fees were earned since, the next share is of the supply left
+        let amount0 =
+            Uint128::from(10_000u128).multiply_ratio(after.reserve0, token_info.total_supply);
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                pool.clone(),
+                &withdraw(amount0 + Uint128::one()),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::SlippageExceeded {
+                amount_out: amount0,
+                amount_out_min: amount0 + Uint128::one(),
+            }
+            .to_string()
+        );
+        assert_eq!(lp_balance(&suite.app, &fee_to), fee_to_lp);
+
+        // the sent token must be the LP token of a registered pool
+        let err = suite
+            .app
+            .execute_contract(user.clone(), usdc.clone(), &withdraw(Uint128::zero()), &[])
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            vault::ContractError::PoolNotExisted {}.to_string()
+        );
+    }
+
+    #[test]
+    fn pools_only_trust_the_vault_of_their_factory() {
+        let mut suite = setup();
+        let deadline = deadline(&suite.app);
+        let (user, vault, usdc) = (suite.user.clone(), suite.vault.clone(), suite.usdc.clone());
+        let (osmo, usdc_asset) = (native_asset(NATIVE_DENOM), cw20_asset(&usdc));
+
+        let pool = suite.create_pool_with(
+            osmo.clone(),
+            usdc_asset.clone(),
+            PoolType::ConstantProduct,
+            30,
+            None,
+        );
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                usdc.clone(),
+                &cw20_base::msg::ExecuteMsg::IncreaseAllowance {
+                    spender: vault.to_string(),
+                    amount: Uint128::from(10_000u128),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .app
+            .execute_contract(
+                user.clone(),
+                vault.clone(),
+                &VaultExecuteMsg::AddLiquidity(AddLiquidityParams {
+                    pool_address: pool.to_string(),
+                    token_a: osmo.clone(),
+                    token_b: usdc_asset.clone(),
+                    amount_a_desired: Uint128::from(10_000u128),
+                    amount_b_desired: Uint128::from(10_000u128),
+                    amount_a_min: Uint128::zero(),
+                    amount_b_min: Uint128::zero(),
+                    address_to: user.to_string(),
+                    deadline,
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &coins(10_000, NATIVE_DENOM),
+            )
+            .unwrap();
+
+        // a vault instantiated by the user, it could report any reserves and fee recipient
+        let vault_code_id = suite.app.contract_data(&vault).unwrap().code_id;
+        let fake_vault = suite
+            .app
+            .instantiate_contract(
+                vault_code_id,
+                user.clone(),
+                &Empty {},
+                &[],
+                "fake vault",
+                None,
+            )
+            .unwrap();
+
+        let err = suite
+            .app
+            .execute_contract(
+                user.clone(),
+                pool.clone(),
+                &cw20_base::msg::ExecuteMsg::Send {
+                    contract: pool.to_string(),
+                    amount: Uint128::from(1_000u128),
+                    msg: to_binary(&packages::pool_msg::RemoveLiquidityPoolParams {
+                        vault_contract_addresss: fake_vault.to_string(),
+                        amount_a_min: Uint128::zero(),
+                        amount_b_min: Uint128::zero(),
+                        address_to: user.to_string(),
+                        deadline,
+                        single_token_out: None,
+                    })
+                    .unwrap(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            uniswapv2_pool::ContractError::Unauthorized {}.to_string()
+        );
+
+        let err = suite
+            .app
+            .execute_contract(
+                fake_vault.clone(),
+                pool.clone(),
+                &PoolExecuteMsg::Mint(packages::pool_msg::MintRecieveParams {
+                    to: user.to_string(),
+                    amount0: Uint128::from(1_000u128),
+                    amount1: Uint128::from(1_000u128),
+                    on_receive: None,
+                    min_liquidity: None,
+                }),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            uniswapv2_pool::ContractError::Unauthorized {}.to_string()
+        );
+
+        let token_info: cw20::TokenInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(pool.clone(), &PoolQueryMsg::TokenInfo {})
+            .unwrap();
+        assert_eq!(token_info.total_supply, Uint128::from(10_000u128));
+    }
+}
+
+#[cfg(test)]
+mod helpers {
+    use cosmwasm_std::{coins, Addr, DenomMetadata, DenomUnit, Empty, Uint128};
+    use cw_multi_test::{
+        App, AppBuilder, BankKeeper, ContractWrapper, Executor, MockAddressGenerator,
+        MockApiBech32, WasmKeeper,
+    };
+    use factory::msg::{PoolParams, PoolType};
+    use packages::asset::AssetInfo;
+
+    /// App with bech32 addresses, so contracts get the same instantiate2 addresses as on chain
+    pub type TestApp = App<BankKeeper, MockApiBech32>;
+
+    pub fn mock_app() -> TestApp {
+        AppBuilder::new()
+            .with_api(MockApiBech32::new("osmo"))
+            .with_wasm(WasmKeeper::new().with_address_generator(MockAddressGenerator))
+            .build(|router, _, storage| {
+                let unit = |denom: &str, exponent: u32| DenomUnit {
+                    denom: denom.to_string(),
+                    exponent,
+                    aliases: vec![],
+                };
+                router
+                    .bank
+                    .set_denom_metadata(
+                        storage,
+                        NATIVE_DENOM.to_string(),
+                        DenomMetadata {
+                            base: NATIVE_DENOM.to_string(),
+                            display: String::from("osmo"),
+                            denom_units: vec![unit(NATIVE_DENOM, 0), unit("osmo", 6)],
+                            symbol: String::from("OSMO"),
+                            ..DenomMetadata::default()
+                        },
+                    )
+                    .unwrap();
+            })
+    }
+
+    /// Native denom every test user is funded with
+    pub const NATIVE_DENOM: &str = "uosmo";
+
+    /// Deadline a minute after the current block
+    pub fn deadline(app: &TestApp) -> Uint128 {
+        Uint128::from(app.block_info().time.seconds() + 60)
+    }
+
+    pub fn native_asset(denom: &str) -> AssetInfo {
+        AssetInfo::Native {
+            denom: denom.to_string(),
+        }
+    }
+
+    pub fn cw20_asset(token: &Addr) -> AssetInfo {
+        AssetInfo::Cw20 {
+            contract_addr: token.to_string(),
+        }
+    }
+
+    pub struct Suite {
+        pub app: TestApp,
+        pub factory_owner: Addr,
+        pub user: Addr,
+        pub vault: Addr,
+        pub factory: Addr,
+        pub usdc: Addr,
+        pub usdt: Addr,
+    }
+
+    /// Instantiates a cw20 token and mints `amount` of it to every address in `holders`
+    pub fn instantiate_token(
+        app: &mut TestApp,
+        symbol: &str,
+        holders: &[&Addr],
+        amount: u128,
+    ) -> Addr {
+        let code_id = app.store_code(Box::new(ContractWrapper::new(
+            cw20_base::contract::execute,
+            cw20_base::contract::instantiate,
+            cw20_base::contract::query,
+        )));
+
+        let minter = app
+            .api()
+            .addr_make(&format!("{}_minter", symbol.to_lowercase()));
+
+        let token = app
+            .instantiate_contract(
+                code_id,
+                minter.clone(),
+                &cw20_base::msg::InstantiateMsg {
+                    name: symbol.to_lowercase(),
+                    symbol: symbol.to_string(),
+                    decimals: 6,
+                    initial_balances: vec![],
+                    mint: Some(cw20::MinterResponse {
+                        minter: minter.to_string(),
+                        cap: None,
+                    }),
+                    marketing: None,
+                },
+                &[],
+                symbol,
+                None,
+            )
+            .unwrap();
+
+        for holder in holders {
+            app.execute_contract(
+                minter.clone(),
+                token.clone(),
+                &cw20_base::msg::ExecuteMsg::Mint {
+                    recipient: holder.to_string(),
+                    amount: Uint128::from(amount),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        token
+    }
+
+    /// Stores the constant product pool code, with its migrate entry point
+    pub fn store_pool_code(app: &mut TestApp) -> u64 {
+        app.store_code(Box::new(
+            ContractWrapper::new(
+                uniswapv2_pool::contract::execute,
+                uniswapv2_pool::contract::instantiate,
+                uniswapv2_pool::contract::query,
+            )
+            .with_migrate(uniswapv2_pool::contract::migrate),
+        ))
+    }
+
+    /// Sets up a vault with a registered factory, two funded cw20 tokens and a funded native denom
+    pub fn setup() -> Suite {
+        let mut app = mock_app();
+
+        let vault_owner = app.api().addr_make("vault_owner");
+        let factory_owner = app.api().addr_make("factory_owner");
+        let user = app.api().addr_make("liquidity_provider");
+
+        app.init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &user, coins(1_000_000, NATIVE_DENOM))
+                .unwrap()
+        });
+
+        let vault_code_id = app.store_code(Box::new(
+            ContractWrapper::new(
+                vault::contract::execute,
+                vault::contract::instantiate,
+                vault::contract::query,
+            )
+            .with_reply(vault::contract::reply),
+        ));
+
+        let vault = app
+            .instantiate_contract(
+                vault_code_id,
+                vault_owner.clone(),
+                &Empty {},
+                &[],
+                "vault contract",
+                None,
+            )
+            .unwrap();
+
+        let usdc = instantiate_token(&mut app, "USDC", &[&user], 1_000_000);
+        let usdt = instantiate_token(&mut app, "USDT", &[&user], 1_000_000);
+
+        let pool_code_id = store_pool_code(&mut app);
+
+        let factory_code_id = app.store_code(Box::new(
+            ContractWrapper::new(
+                factory::contract::execute,
+                factory::contract::instantiate,
+                factory::contract::query,
+            )
+            .with_reply(factory::contract::reply),
+        ));
+
+        let factory = app
+            .instantiate_contract(
+                factory_code_id,
+                factory_owner.clone(),
+                &factory::msg::InstantiateMsg {
+                    pool_code_ids: vec![factory::msg::PoolCodeId {
+                        pool_type: factory::msg::PoolType::ConstantProduct,
+                        code_id: pool_code_id,
+                    }],
+                    vault_contract: vault.to_string(),
+                },
+                &[],
+                "factory contract",
+                None,
+            )
+            .unwrap();
+
+        app.execute_contract(
+            vault_owner,
+            vault.clone(),
+            &vault::msg::ExecuteMsg::RegisterFactory {
+                factory_address: factory.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        Suite {
+            app,
+            factory_owner,
+            user,
+            vault,
+            factory,
+            usdc,
+            usdt,
+        }
+    }
+
+    impl Suite {
+        /// Creates a 0.3% constant product pool for `token_a`/`token_b` and returns its address
+        pub fn create_pool(&mut self, token_a: &Addr, token_b: &Addr) -> Addr {
+            self.create_pool_with(
+                cw20_asset(token_a),
+                cw20_asset(token_b),
+                PoolType::ConstantProduct,
+                30,
+                None,
+            )
+        }
+
+        /// Creates a pool of `pool_type` for `token_a`/`token_b` and returns its address
+        pub fn create_pool_with(
+            &mut self,
+            token_a: AssetInfo,
+            token_b: AssetInfo,
+            pool_type: PoolType,
+            fee_bps: u16,
+            params: Option<PoolParams>,
+        ) -> Addr {
+            let res = self
+                .app
+                .execute_contract(
+                    self.factory_owner.clone(),
+                    self.factory.clone(),
+                    &factory::msg::ExecuteMsg::CreatePool {
+                        token_a,
+                        token_b,
+                        pool_type,
+                        fee_bps,
+                        params,
+                    },
+                    &[],
+                )
+                .unwrap();
+
+            // the factory reports the address of every pool type, only constant product pools reach the vault
+            let pool_address = res
+                .events
+                .iter()
+                .filter(|ev| ev.ty == "wasm")
+                .flat_map(|ev| ev.attributes.iter())
+                .find(|attr| attr.key == "pool_address")
+                .map(|attr| attr.value.clone())
+                .unwrap();
+
+            Addr::unchecked(pool_address)
+        }
+    }
+}
+
+#[cfg(test)]
+mod factory_tests {
+    use super::helpers::{cw20_asset, instantiate_token, setup, store_pool_code};
+    use cosmwasm_std::{to_binary, Addr, Uint128};
+    use cw_multi_test::{ContractWrapper, Executor};
+    use factory::msg::{MigratePoolsTarget, PairKey, PoolParams, PoolType};
+    use packages::pool_msg::{AmountOutParams, FeeInfoResponse, PoolExecuteMsg, PoolQueryMsg};
+
+    #[test]
+    fn create_pool_rejects_duplicate_pair() {
+        let mut suite = setup();
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+
+        let pool = suite.create_pool(&usdc, &usdt);
+
+        // tokens are registered in the vault in their canonical order
+        let pool_data: packages::vault_msg::PoolDataResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.vault.clone(),
+                &vault::msg::QueryMsg::QueryPoolData {
+                    pool_address: pool.to_string(),
+                },
+            )
+            .unwrap();
+        assert!(pool_data.token0 < pool_data.token1);
+
+        // the same pair cannot be created again, in either order
+        for (token_a, token_b) in [(&usdc, &usdt), (&usdt, &usdc)] {
+            let err = suite
+                .app
+                .execute_contract(
+                    suite.factory_owner.clone(),
+                    suite.factory.clone(),
+                    &factory::msg::ExecuteMsg::CreatePool {
+                        token_a: cw20_asset(token_a),
+                        token_b: cw20_asset(token_b),
+                        pool_type: PoolType::ConstantProduct,
+                        fee_bps: 30,
+                        params: None,
+                    },
+                    &[],
+                )
+                .unwrap_err();
+
+            assert_eq!(
+                err.root_cause().to_string(),
+                factory::ContractError::PairAlreadyExists {
+                    token0: pool_data.token0.to_string(),
+                    token1: pool_data.token1.to_string(),
+                }
+                .to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn query_pairs_pair_and_config() {
+        let mut suite = setup();
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+        let dai = instantiate_token(&mut suite.app, "DAI", &[], 0);
+
+        let usdc_usdt = suite.create_pool(&usdc, &usdt);
+        let dai_usdc = suite.create_pool(&dai, &usdc);
+
+        let config: factory::msg::ConfigResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(suite.factory.clone(), &factory::msg::QueryMsg::Config {})
+            .unwrap();
+        assert_eq!(config.owner, suite.factory_owner.to_string());
+        assert_eq!(config.vault_contract, suite.vault.to_string());
+
+        // a pair can be looked up with its tokens in any order
+        let pair: factory::msg::PairResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.factory.clone(),
+                &factory::msg::QueryMsg::Pair {
+                    token_a: cw20_asset(&usdt),
+                    token_b: cw20_asset(&usdc),
+                    pool_type: PoolType::ConstantProduct,
+                    fee_bps: 30,
+                },
+            )
+            .unwrap();
+        assert_eq!(pair.pool_address, usdc_usdt.to_string());
+
+        let first_page: factory::msg::PairsResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.factory.clone(),
+                &factory::msg::QueryMsg::Pairs {
+                    start_after: None,
+                    limit: Some(1),
+                },
+            )
+            .unwrap();
+        assert_eq!(first_page.pairs.len(), 1);
+
+        let last = first_page.pairs.last().unwrap();
+        let second_page: factory::msg::PairsResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.factory.clone(),
+                &factory::msg::QueryMsg::Pairs {
+                    start_after: Some(factory::msg::PairKey {
+                        token_a: last.token0.clone(),
+                        token_b: last.token1.clone(),
+                        pool_type: last.pool_type,
+                        fee_bps: last.fee_bps,
+                    }),
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(second_page.pairs.len(), 1);
+
+        let mut pools: Vec<String> = first_page
+            .pairs
+            .into_iter()
+            .chain(second_page.pairs)
+            .map(|pair| pair.pool_address)
+            .collect();
+        pools.sort();
+        let mut expected = vec![usdc_usdt.to_string(), dai_usdc.to_string()];
+        expected.sort();
+        assert_eq!(pools, expected);
+    }
+
+    #[test]
+    fn create_pools_of_different_types_for_same_pair() {
+        let mut suite = setup();
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+
+        let v3_pool_code_id = suite.app.store_code(Box::new(ContractWrapper::new(
+            uniswapv3_pool::contract::execute,
+            uniswapv3_pool::contract::instantiate,
+            uniswapv3_pool::contract::query,
+        )));
+
+        // only the owner manages the code id table
+        suite
+            .app
+            .execute_contract(
+                suite.vault.clone(),
+                suite.factory.clone(),
+                &factory::msg::ExecuteMsg::SetPoolCodeId {
+                    pool_type: PoolType::ConcentratedLiquidity,
+                    code_id: v3_pool_code_id,
+                },
+                &[],
+            )
+            .unwrap_err();
+
+        suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                suite.factory.clone(),
+                &factory::msg::ExecuteMsg::SetPoolCodeId {
+                    pool_type: PoolType::ConcentratedLiquidity,
+                    code_id: v3_pool_code_id,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // params of another pool type are rejected
+        let err = suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                suite.factory.clone(),
+                &factory::msg::ExecuteMsg::CreatePool {
+                    token_a: cw20_asset(&usdc),
+                    token_b: cw20_asset(&usdt),
+                    pool_type: PoolType::ConcentratedLiquidity,
+                    fee_bps: 30,
+                    params: Some(PoolParams::StableSwap { amp: 100 }),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            factory::ContractError::InvalidPoolParams {
+                pool_type: PoolType::ConcentratedLiquidity.as_str().to_string(),
+            }
+            .to_string()
+        );
+
+        // pool types without a code id cannot be created
+        let err = suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                suite.factory.clone(),
+                &factory::msg::ExecuteMsg::CreatePool {
+                    token_a: cw20_asset(&usdc),
+                    token_b: cw20_asset(&usdt),
+                    pool_type: PoolType::StableSwap,
+                    fee_bps: 4,
+                    params: Some(PoolParams::StableSwap { amp: 100 }),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            factory::ContractError::PoolCodeIdNotFound {
+                pool_type: PoolType::StableSwap.as_str().to_string(),
+            }
+            .to_string()
+        );
+
+        let v2_pool = suite.create_pool(&usdc, &usdt);
+        let v3_pool = suite.create_pool_with(
+            cw20_asset(&usdc),
+            cw20_asset(&usdt),
+            PoolType::ConcentratedLiquidity,
+            30,
+            Some(PoolParams::ConcentratedLiquidity { tick_spacing: 60 }),
+        );
+        assert_ne!(v2_pool, v3_pool);
+
+        // only the constant product pool is registered in the vault
+        let pool_data = |pool: &Addr| {
+            suite
+                .app
+                .wrap()
+                .query_wasm_smart::<packages::vault_msg::PoolDataResponse>(
+                    suite.vault.clone(),
+                    &vault::msg::QueryMsg::QueryPoolData {
+                        pool_address: pool.to_string(),
+                    },
+                )
+        };
+        assert!(pool_data(&v2_pool).is_ok());
+        assert!(pool_data(&v3_pool).is_err());
+
+        let pairs: factory::msg::PairsResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.factory.clone(),
+                &factory::msg::QueryMsg::Pairs {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        let pool_types: Vec<PoolType> = pairs.pairs.iter().map(|pair| pair.pool_type).collect();
+        assert_eq!(
+            pool_types,
+            vec![PoolType::ConcentratedLiquidity, PoolType::ConstantProduct]
+        );
+
+        // the fee of every pool type can be updated through the factory
+        suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                suite.factory.clone(),
+                &factory::msg::ExecuteMsg::UpdatePoolFee {
+                    pair: PairKey {
+                        token_a: cw20_asset(&usdc),
+                        token_b: cw20_asset(&usdt),
+                        pool_type: PoolType::ConcentratedLiquidity,
+                        fee_bps: 30,
+                    },
+                    fee_bps: 5,
+                },
+                &[],
+            )
+            .unwrap();
+        let fee_info: FeeInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(v3_pool, &uniswapv3_pool::msg::QueryMsg::FeeInfo {})
+            .unwrap();
+        assert_eq!(fee_info.fee_bps, 5);
+        assert_eq!(fee_info.governance, suite.factory.to_string());
+    }
+
+    #[test]
+    fn fee_tiers_and_fee_updates() {
+        let mut suite = setup();
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+
+        let low_fee_pool = suite.create_pool_with(
+            cw20_asset(&usdc),
+            cw20_asset(&usdt),
+            PoolType::ConstantProduct,
+            5,
+            None,
+        );
+        let high_fee_pool = suite.create_pool(&usdc, &usdt);
+
+        let amount_out = |suite: &super::helpers::Suite, pool: &cosmwasm_std::Addr| -> Uint128 {
+            suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    pool.clone(),
+                    &PoolQueryMsg::GetAmountOut(AmountOutParams {
+                        amount_in: Uint128::from(1_000u128),
+                        reserve_in: Uint128::from(1_000_000u128),
+                        reserve_out: Uint128::from(1_000_000u128),
+                    }),
+                )
+                .unwrap()
+        };
+
+        // 0.05% and 0.3% of the input are kept as fee
+        assert_eq!(amount_out(&suite, &low_fee_pool), Uint128::from(998u128));
+        assert_eq!(amount_out(&suite, &high_fee_pool), Uint128::from(996u128));
+
+        // quotes at reserves whose products overflow Uint128 are computed in Uint256
+        let reserve = Uint128::from(10u128.pow(30));
+        let quote_out: Uint128 = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                high_fee_pool.clone(),
+                &PoolQueryMsg::GetAmountOut(AmountOutParams {
+                    amount_in: Uint128::from(10u128.pow(20)),
+                    reserve_in: reserve,
+                    reserve_out: reserve,
+                }),
+            )
+            .unwrap();
+        assert_eq!(quote_out, Uint128::from(99_699_999_990_059_910_000u128));
+        let quote_in: Uint128 = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                high_fee_pool.clone(),
+                &PoolQueryMsg::GetAmountIn(packages::pool_msg::AmountInParams {
+                    amount_out: Uint128::from(10u128.pow(20)),
+                    reserve_in: reserve,
+                    reserve_out: reserve,
+                }),
+            )
+            .unwrap();
+        assert_eq!(quote_in, Uint128::from(100_300_902_718_154_463_392u128));
+
+        // fees above the maximum are rejected
+        let err = suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                suite.factory.clone(),
+                &factory::msg::ExecuteMsg::UpdatePoolFee {
+                    pair: PairKey {
+                        token_a: cw20_asset(&usdc),
+                        token_b: cw20_asset(&usdt),
+                        pool_type: PoolType::ConstantProduct,
+                        fee_bps: 30,
+                    },
+                    fee_bps: 2_000,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            factory::ContractError::InvalidFee { fee_bps: 2_000 }.to_string()
+        );
+
+        // the pool only accepts fee updates from the factory
+        suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                high_fee_pool.clone(),
+                &PoolExecuteMsg::UpdateFee { fee_bps: 100 },
+                &[],
+            )
+            .unwrap_err();
+
+        suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                suite.factory.clone(),
+                &factory::msg::ExecuteMsg::UpdatePoolFee {
+                    pair: PairKey {
+                        token_a: cw20_asset(&usdt),
+                        token_b: cw20_asset(&usdc),
+                        pool_type: PoolType::ConstantProduct,
+                        fee_bps: 30,
+                    },
+                    fee_bps: 100,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let fee_info: FeeInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(high_fee_pool.clone(), &PoolQueryMsg::FeeInfo {})
+            .unwrap();
+        assert_eq!(fee_info.fee_bps, 100);
+        assert_eq!(fee_info.governance, suite.factory.to_string());
+        assert_eq!(amount_out(&suite, &high_fee_pool), Uint128::from(989u128));
+
+        // the registry is re-keyed under the new fee tier
+        let pair: factory::msg::PairResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.factory.clone(),
+                &factory::msg::QueryMsg::Pair {
+                    token_a: cw20_asset(&usdc),
+                    token_b: cw20_asset(&usdt),
+                    pool_type: PoolType::ConstantProduct,
+                    fee_bps: 100,
+                },
+            )
+            .unwrap();
+        assert_eq!(pair.pool_address, high_fee_pool.to_string());
+    }
+
+    #[test]
+    fn migrate_pools_in_batches() {
+        let mut suite = setup();
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+
+        let pools = vec![
+            suite.create_pool_with(
+                cw20_asset(&usdc),
+                cw20_asset(&usdt),
+                PoolType::ConstantProduct,
+                5,
+                None,
+            ),
+            suite.create_pool(&usdc, &usdt),
+        ];
+
+        let new_code_id = store_pool_code(&mut suite.app);
+
+        for pool in &pools {
+            let info = suite.app.wrap().query_wasm_contract_info(pool).unwrap();
+            assert_eq!(info.admin, Some(suite.factory.to_string()));
+        }
+
+        let migrate_pools = |target: MigratePoolsTarget| factory::msg::ExecuteMsg::MigratePools {
+            new_code_id,
+            target,
+            msg: to_binary(&packages::pool_msg::PoolMigrateMsg {}).unwrap(),
+        };
+
+        // only the owner can migrate pools
+        suite
+            .app
+            .execute_contract(
+                suite.vault.clone(),
+                suite.factory.clone(),
+                &migrate_pools(MigratePoolsTarget::Pools {
+                    pool_addresses: vec![pools[0].to_string()],
+                }),
+                &[],
+            )
+            .unwrap_err();
+
+        // first page holds the 5 bps pool, the second one the 30 bps pool
+        let res = suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                suite.factory.clone(),
+                &migrate_pools(MigratePoolsTarget::All {
+                    pool_type: PoolType::ConstantProduct,
+                    start_after: None,
+                    limit: Some(1),
+                }),
+                &[],
+            )
+            .unwrap();
+        let last_scanned: PairKey = cosmwasm_std::from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(last_scanned.fee_bps, 5);
+
+        let code_ids = |suite: &super::helpers::Suite| -> Vec<u64> {
+            pools
+                .iter()
+                .map(|pool| {
+                    suite
+                        .app
+                        .wrap()
+                        .query_wasm_contract_info(pool)
+                        .unwrap()
+                        .code_id
+                })
+                .collect()
+        };
+        assert_eq!(code_ids(&suite)[0], new_code_id);
+        assert_ne!(code_ids(&suite)[1], new_code_id);
+
+        suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                suite.factory.clone(),
+                &migrate_pools(MigratePoolsTarget::All {
+                    pool_type: PoolType::ConstantProduct,
+                    start_after: Some(last_scanned),
+                    limit: Some(1),
+                }),
+                &[],
+            )
+            .unwrap();
+        assert_eq!(code_ids(&suite), vec![new_code_id, new_code_id]);
+    }
+
+    #[test]
+    fn predict_pool_address_matches_created_pool() {
+        let mut suite = setup();
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+
+        let predict =
+            |suite: &super::helpers::Suite, token_a: &Addr, token_b: &Addr, fee_bps: u16| {
+                suite
+                    .app
+                    .wrap()
+                    .query_wasm_smart::<factory::msg::PredictPoolAddressResponse>(
+                        suite.factory.clone(),
+                        &factory::msg::QueryMsg::PredictPoolAddress {
+                            token_a: cw20_asset(token_a),
+                            token_b: cw20_asset(token_b),
+                            pool_type: PoolType::ConstantProduct,
+                            fee_bps,
+                        },
+                    )
+                    .unwrap()
+            };
+
+        // the prediction does not depend on the token order, but on the fee tier
+        let predicted = predict(&suite, &usdc, &usdt, 30);
+        assert_eq!(predict(&suite, &usdt, &usdc, 30), predicted);
+        assert_ne!(predict(&suite, &usdc, &usdt, 5), predicted);
+
+        let pool = suite.create_pool(&usdt, &usdc);
+        assert_eq!(pool.to_string(), predicted.pool_address);
+
+        let pair: factory::msg::PairResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.factory.clone(),
+                &factory::msg::QueryMsg::Pair {
+                    token_a: cw20_asset(&usdc),
+                    token_b: cw20_asset(&usdt),
+                    pool_type: PoolType::ConstantProduct,
+                    fee_bps: 30,
+                },
+            )
+            .unwrap();
+        assert_eq!(pair.pool_address, predicted.pool_address);
+    }
+
+    #[test]
+    fn fee_tier_is_reusable_after_its_pool_moved() {
+        let mut suite = setup();
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+
+        let predict = |suite: &super::helpers::Suite, fee_bps: u16| {
+            suite
+                .app
+                .wrap()
+                .query_wasm_smart::<factory::msg::PredictPoolAddressResponse>(
+                    suite.factory.clone(),
+                    &factory::msg::QueryMsg::PredictPoolAddress {
+                        token_a: cw20_asset(&usdc),
+                        token_b: cw20_asset(&usdt),
+                        pool_type: PoolType::ConstantProduct,
+                        fee_bps,
+                    },
+                )
+                .unwrap()
+        };
+
+        let pool = suite.create_pool(&usdc, &usdt);
+        let created = predict(&suite, 30);
+        assert_eq!(created.pool_address, pool.to_string());
+
+        suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                suite.factory.clone(),
+                &factory::msg::ExecuteMsg::UpdatePoolFee {
+                    pair: PairKey {
+                        token_a: cw20_asset(&usdc),
+                        token_b: cw20_asset(&usdt),
+                        pool_type: PoolType::ConstantProduct,
+                        fee_bps: 30,
+                    },
+                    fee_bps: 5,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // the moved pool keeps its address and salt in the new tier, the old tier gets a new address
+        assert_eq!(predict(&suite, 5), created);
+        let next = predict(&suite, 30);
+        assert_ne!(next.pool_address, created.pool_address);
+
+        let new_pool = suite.create_pool(&usdt, &usdc);
+        assert_eq!(new_pool.to_string(), next.pool_address);
+        assert_eq!(predict(&suite, 30), next);
+
+        let pairs: factory::msg::PairsResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.factory.clone(),
+                &factory::msg::QueryMsg::Pairs {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        let mut pools: Vec<(u16, String)> = pairs
+            .pairs
+            .into_iter()
+            .map(|pair| (pair.fee_bps, pair.pool_address))
+            .collect();
+        pools.sort();
+        assert_eq!(
+            pools,
+            vec![(5, pool.to_string()), (30, new_pool.to_string())]
+        );
+    }
+
+    #[test]
+    fn lp_token_metadata_from_underlying_tokens() {
+        let mut suite = setup();
+        let (usdc, usdt) = (suite.usdc.clone(), suite.usdt.clone());
+
+        let pool = suite.create_pool(&usdc, &usdt);
+
+        // the LP token follows the canonical token order
+        let expected_symbol = if usdc < usdt {
+            "USDC-USDT-LP"
+        } else {
+            "USDT-USDC-LP"
+        };
+        let token_info: cw20::TokenInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(pool.clone(), &PoolQueryMsg::TokenInfo {})
+            .unwrap();
+        assert_eq!(token_info.name, expected_symbol);
+        assert_eq!(token_info.symbol, expected_symbol);
+        assert_eq!(token_info.decimals, 6);
+
+        let marketing: cw20::MarketingInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(pool.clone(), &PoolQueryMsg::MarketingInfo {})
+            .unwrap();
+        assert_eq!(marketing.marketing, Some(suite.factory_owner.clone()));
+        assert!(marketing.description.unwrap().contains("30 bps"));
+
+        // only the marketing address can upload a logo
+        let logo = cw20::Logo::Url("https://example.com/usdc-usdt.png".to_string());
+        suite
+            .app
+            .execute_contract(
+                suite.vault.clone(),
+                pool.clone(),
+                &PoolExecuteMsg::UploadLogo(logo.clone()),
+                &[],
+            )
+            .unwrap_err();
+        suite
+            .app
+            .execute_contract(
+                suite.factory_owner.clone(),
+                pool.clone(),
+                &PoolExecuteMsg::UploadLogo(logo),
+                &[],
+            )
+            .unwrap();
+
+        let marketing: cw20::MarketingInfoResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(pool, &PoolQueryMsg::MarketingInfo {})
+            .unwrap();
+        assert_eq!(
+            marketing.logo,
+            Some(cw20::LogoInfo::Url(
+                "https://example.com/usdc-usdt.png".to_string()
+            ))
+        );
+    }
 }
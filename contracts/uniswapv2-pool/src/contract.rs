@@ -3,17 +3,21 @@ use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult, Uint128,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::MarketingInfoResponse;
 use cw20_base::allowances::query_allowance;
 use cw20_base::contract::{
-    execute_burn, execute_mint, execute_send, query_balance, query_token_info,
+    execute_burn, execute_mint, execute_send, execute_update_marketing, execute_upload_logo,
+    query_balance, query_download_logo, query_marketing_info, query_token_info,
 };
-use cw20_base::state::{MinterData, TokenInfo, BALANCES, TOKEN_INFO};
+use cw20_base::state::{MinterData, TokenInfo, BALANCES, MARKETING_INFO, TOKEN_INFO};
 
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{PoolConfig, K_LAST, POOL_CONFIG};
 use packages::pool_msg::{
-    AmountInParams, AmountOutParams, Cw20ReceiveMsg, MintRecieveParams, VaultMsgEnums,
+    AmountInParams, AmountOutParams, Cw20ReceiveMsg, FactoryConfigResponse, FactoryMsgEnums,
+    MintRecieveParams, VaultMsgEnums, FEE_DENOMINATOR, MAX_FEE_BPS,
 };
 
 use num::integer::Roots;
@@ -24,7 +28,7 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     _msg: InstantiateMsg,
@@ -38,20 +42,109 @@ pub fn instantiate(
         total_supply: Uint128::zero(),
         // set self as minter, so we can properly execute mint and burn
         mint: Some(MinterData {
-            minter: _env.contract.address,
+            minter: _env.contract.address.clone(),
             cap: None,
         }),
     };
     TOKEN_INFO.save(deps.storage, &data)?;
 
+    // store marketing info of the LP token, the logo is uploaded through cw20-base so it gets validated
+    if let Some(marketing) = _msg.marketing {
+        let marketing_address = marketing
+            .marketing
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+
+        MARKETING_INFO.save(
+            deps.storage,
+            &MarketingInfoResponse {
+                project: marketing.project,
+                description: marketing.description,
+                marketing: marketing_address.clone(),
+                logo: None,
+            },
+        )?;
+
+        if let Some(logo) = marketing.logo {
+            let sender = match marketing_address {
+                Some(sender) => sender,
+                None => {
+                    return Err(ContractError::CustomError {
+                        val: String::from("A logo requires a marketing address"),
+                    })
+                }
+            };
+
+            let information = MessageInfo {
+                sender,
+                funds: vec![],
+            };
+            execute_upload_logo(deps.branch(), _env.clone(), information, logo)?;
+        }
+    }
+
+    if _msg.fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::InvalidFee {
+            fee_bps: _msg.fee_bps,
+        });
+    }
+
+    // the instantiator (factory) governs the fee of the pool
+    POOL_CONFIG.save(
+        deps.storage,
+        &PoolConfig {
+            governance: info.sender.to_string(),
+            fee_bps: _msg.fee_bps,
+        },
+    )?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("owner", info.sender)
+        .add_attribute("fee_bps", _msg.fee_bps.to_string()))
 }
 
+/**
+ * Migrate
+ *
+ * Pools are instantiated with their factory as admin, which migrates them to new code.
+ * The stored cw2 info must belong to this contract and must not be newer than the code
+ * being migrated to, so a pool can never be downgraded or swapped for another contract.
+ */
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    unimplemented!()
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigration {
+            val: format!("cannot migrate from {}", stored.contract),
+        });
+    }
+
+    let stored_version =
+        semver::Version::parse(&stored.version).map_err(|_| ContractError::InvalidMigration {
+            val: format!("invalid stored version {}", stored.version),
+        })?;
+    let new_version =
+        semver::Version::parse(CONTRACT_VERSION).map_err(|_| ContractError::InvalidMigration {
+            val: format!("invalid contract version {}", CONTRACT_VERSION),
+        })?;
+
+    if stored_version > new_version {
+        return Err(ContractError::InvalidMigration {
+            val: format!(
+                "cannot downgrade from {} to {}",
+                stored_version, new_version
+            ),
+        });
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored_version.to_string())
+        .add_attribute("to_version", new_version.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -66,7 +159,7 @@ pub fn execute(
             execute::execute_pool_mint(_deps, _env, _info, mint_recieve_params)
         }
         ExecuteMsg::Receive(cw20_receive_msg) => {
-            execute::execute_burn_lp_tokens(_deps, _env, _info, cw20_receive_msg)
+            execute::execute_forward_lp_tokens(_deps, _env, _info, cw20_receive_msg)
         }
         ExecuteMsg::Send {
             contract,
@@ -74,22 +167,129 @@ pub fn execute(
             msg,
         } => execute_send(_deps, _env, _info, contract, amount, msg)
             .map_err(|_| ContractError::Unauthorized {}),
+        ExecuteMsg::UpdateFee { fee_bps } => execute::execute_update_fee(_deps, _env, _info, fee_bps),
+        ExecuteMsg::UpdateMarketing {
+            project,
+            description,
+            marketing,
+        } => Ok(execute_update_marketing(
+            _deps,
+            _env,
+            _info,
+            project,
+            description,
+            marketing,
+        )?),
+        ExecuteMsg::UploadLogo(logo) => Ok(execute_upload_logo(_deps, _env, _info, logo)?),
+        ExecuteMsg::BurnFromVault { amount } => {
+            execute::execute_burn_from_vault(_deps, _env, _info, amount)
+        }
     }
 }
 
 pub mod execute {
 
     use packages::pool_msg::{PoolDataResponse, RemoveLiquidityPoolParams};
-    use std::ops::{Add, Div, Mul, Sub};
+    use std::ops::{Add, Mul, Sub};
 
     use super::*;
-    use cosmwasm_std::{from_binary, QueryRequest, WasmMsg, WasmQuery};
+    use cosmwasm_std::{from_binary, Isqrt, QueryRequest, StdError, Uint256, WasmQuery};
+    use packages::vault_msg::{FeeToResponse, VaultHookMsg, WithdrawLiquidityParams};
 
     /**
-     * Execute Burn LP Tokens
+     * Returns the vault of the factory that instantiated the pool, the only contract the pool reads its
+     * reserves and the protocol fee recipient from.
+     */
+    fn vault_address(_deps: Deps) -> Result<String, ContractError> {
+        let governance = POOL_CONFIG.load(_deps.storage)?.governance;
+        let factory_config: FactoryConfigResponse = match _deps
+            .querier
+            .query_wasm_smart(governance, &FactoryMsgEnums::Config {})
+        {
+            Ok(config) => config,
+            Err(_) => return Err(ContractError::QueryFailed {}),
+        };
+
+        Ok(factory_config.vault_contract)
+    }
+
+    /**
+     * Mint Protocol Fee
      *
-     * This function is responsible for executing the burning of LP tokens, which involves
-     *  burning of tokens.
+     * Uniswap V2 fee switch: when the vault has a `fee_to` address, mints it 1/6th of the growth of
+     * `sqrt(reserve0 * reserve1)` since the last liquidity event, that is 1/6th of the swap fees earned
+     * by the liquidity providers. Called before every mint and burn, with the reserves before them.
+     *
+     * @returns Whether the fee is on, in which case `K_LAST` must be updated after the event, and the
+     * amount of LP tokens minted to `fee_to`.
+     */
+    fn mint_protocol_fee(
+        mut _deps: DepsMut,
+        _env: &Env,
+        _vault_address: &str,
+        _reserve0: Uint128,
+        _reserve1: Uint128,
+    ) -> Result<(bool, Uint128), ContractError> {
+        let fee_to: Result<FeeToResponse, _> =
+            _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: _vault_address.to_string(),
+                msg: to_binary(&VaultMsgEnums::FeeTo {})?,
+            }));
+
+        let fee_to = match fee_to {
+            Ok(data) => data.fee_to,
+            Err(_) => return Err(ContractError::QueryFailed {}),
+        };
+        let k_last = K_LAST.may_load(_deps.storage)?.unwrap_or_default();
+
+        match fee_to {
+            Some(fee_to) => {
+                let mut liquidity = Uint128::zero();
+
+                if !k_last.is_zero() {
+                    let root_k = _reserve0.full_mul(_reserve1).isqrt();
+                    let root_k_last = k_last.isqrt();
+
+                    if root_k > root_k_last {
+                        let total_supply = TOKEN_INFO.load(_deps.storage)?.total_supply;
+                        let numerator = Uint256::from(total_supply) * (root_k - root_k_last);
+                        let denominator = root_k * Uint256::from(5u128) + root_k_last;
+                        liquidity =
+                            Uint128::try_from(numerator / denominator).map_err(StdError::from)?;
+                    }
+                }
+
+                if !liquidity.is_zero() {
+                    let information = MessageInfo {
+                        sender: _env.contract.address.clone(),
+                        funds: vec![],
+                    };
+
+                    if execute_mint(_deps.branch(), _env.clone(), information, fee_to, liquidity)
+                        .is_err()
+                    {
+                        return Err(ContractError::MintTokenFailed {});
+                    }
+                }
+
+                Ok((true, liquidity))
+            }
+            None => {
+                if !k_last.is_zero() {
+                    K_LAST.save(_deps.storage, &Uint256::zero())?;
+                }
+
+                Ok((false, Uint128::zero()))
+            }
+        }
+    }
+
+    /**
+     * Execute Forward LP Tokens
+     *
+     * Forwards the LP tokens sent to the pool to the vault of its factory with a
+     * `VaultHookMsg::WithdrawLiquidity` hook. The vault computes the withdrawn amounts from its own
+     * reserves and asks the pool to burn the tokens with `BurnFromVault`.
      *
      * @param _deps            Mutable dependencies for the contract
      * @param _env             Environment information
@@ -98,7 +298,7 @@ pub mod execute {
      *
      * @returns A Result containing a Response or a ContractError in case of failure.
      */
-    pub fn execute_burn_lp_tokens(
+    pub fn execute_forward_lp_tokens(
         mut _deps: DepsMut,
         _env: Env,
         _info: MessageInfo,
@@ -121,83 +321,120 @@ pub mod execute {
         let _remove_liquidity_pool_params: RemoveLiquidityPoolParams =
             from_binary(&_cw20_receive_msg.msg)?;
 
-        // Query pool data from the vault contract
-        let pool_data: Result<PoolDataResponse, _> =
-            _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-                contract_addr: _remove_liquidity_pool_params
-                    .vault_contract_addresss
-                    .clone(),
-                msg: to_binary(&VaultMsgEnums::QueryPoolData {
-                    pool_address: _env.contract.address.to_string(),
-                })?,
-            }));
+        // The LP tokens are only forwarded to the vault of the factory
+        let vault_contract = vault_address(_deps.as_ref())?;
+        if _remove_liquidity_pool_params.vault_contract_addresss != vault_contract {
+            return Err(ContractError::Unauthorized {});
+        }
 
-        match pool_data {
-            // Fetch the total token supply
-            Ok(data) => {
-                let total_supply = match TOKEN_INFO.load(_deps.storage) {
-                    Ok(supply) => supply.total_supply,
-                    Err(_) => {
-                        return Err(ContractError::CustomError {
-                            val: "FetchTotalSupplyFailed".to_string(),
-                        })
-                    }
-                };
+        let withdraw_hook = to_binary(&VaultHookMsg::WithdrawLiquidity(WithdrawLiquidityParams {
+            amount0_min: _remove_liquidity_pool_params.amount_a_min,
+            amount1_min: _remove_liquidity_pool_params.amount_b_min,
+            address_to: _remove_liquidity_pool_params.address_to,
+            deadline: _remove_liquidity_pool_params.deadline,
+            single_token_out: _remove_liquidity_pool_params.single_token_out,
+        }))?;
+
+        let information = MessageInfo {
+            sender: _env.contract.address.clone(),
+            funds: vec![],
+        };
+        let response = execute_send(
+            _deps.branch(),
+            _env,
+            information,
+            vault_contract,
+            pool_balance,
+            withdraw_hook,
+        )?;
+
+        Ok(response.add_attribute("function", "execute_forward_lp_tokens"))
+    }
 
-                  // Calculate the amounts to be removed 
-                let (amount0, amount1) = (
-                    (pool_balance.mul(data.reserve0)).div(total_supply),
-                    (pool_balance.mul(data.reserve1)).div(total_supply),
-                );
+    /**
+     * Execute Burn From Vault
+     *
+     * Burns `_amount` LP tokens held by the vault, which computes the withdrawn amounts itself from the
+     * supply left. The sender must be the vault of the factory that instantiated the pool.
+     * The protocol fee is minted first and `K_LAST` is set to the reserves left after the withdrawal.
+     */
+    pub fn execute_burn_from_vault(
+        mut _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _amount: Uint128,
+    ) -> Result<Response, ContractError> {
+        let vault_contract = vault_address(_deps.as_ref())?;
+        if vault_contract != _info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
 
-                 // Check if the specified minimum amounts are met
-                if _remove_liquidity_pool_params.amount_a_min > amount0
-                    || _remove_liquidity_pool_params.amount_b_min > amount1
-                {
-                    return Err(ContractError::CustomError {
-                        val: String::from("Insufficient A and B amount!"),
-                    });
-                }
+        let data: PoolDataResponse = match _deps.querier.query_wasm_smart(
+            &vault_contract,
+            &VaultMsgEnums::QueryPoolData {
+                pool_address: _env.contract.address.to_string(),
+            },
+        ) {
+            Ok(data) => data,
+            Err(_) => return Err(ContractError::FetchReserveFailed {}),
+        };
 
-                let information = MessageInfo {
-                    sender: _env.contract.address.clone(),
-                    funds: vec![],
-                };
-                // Execute the burn operation
-                let response =
-                    match execute_burn(_deps.branch(), _env.clone(), information, pool_balance) {
-                        Ok(response) => response,
-                        Err(_) => return Err(ContractError::BurnTokenFailed {}),
-                    };
-                // Calculate updated reserve values
-                let (_reserve_a, _reserve_b) =
-                    (data.reserve0.sub(amount0), data.reserve1.sub(amount1));
-                
-                // send msg to vault contract to REmoveLiquidity
-                let _execute_vault_tx = WasmMsg::Execute {
-                    contract_addr: _remove_liquidity_pool_params.vault_contract_addresss,
-                    msg: to_binary(&packages::vault_msg::VaultExecuteMsg::RemoveLiquidity(
-                        packages::vault_msg::RemoveLiquidityParams {
-                            token_a: data.token0,
-                            token_b: data.token1,
-                            reserve_a: _reserve_a,
-                            reserve_b: _reserve_b,
-                            amount_a: amount0,
-                            amount_b: amount1,
-                            address_to: _remove_liquidity_pool_params.address_to,
-                        },
-                    ))?,
-                    funds: vec![],
-                };
+        let (fee_on, protocol_fee) = mint_protocol_fee(
+            _deps.branch(),
+            &_env,
+            &vault_contract,
+            data.reserve0,
+            data.reserve1,
+        )?;
+
+        let total_supply = TOKEN_INFO.load(_deps.storage)?.total_supply;
+        if _amount.is_zero() || _amount > total_supply {
+            return Err(ContractError::InsufficientLiquidity {});
+        }
 
-                Ok(response.add_message(_execute_vault_tx))
-            }
-            Err(_) => {
-                return Err(ContractError::CustomError {
-                    val: "QueryFailed".to_string(),
-                })
-            }
+        let response = match execute_burn(_deps.branch(), _env, _info, _amount) {
+            Ok(response) => response,
+            Err(_) => return Err(ContractError::BurnTokenFailed {}),
+        };
+
+        if fee_on {
+            let reserve0 = data.reserve0 - _amount.multiply_ratio(data.reserve0, total_supply);
+            let reserve1 = data.reserve1 - _amount.multiply_ratio(data.reserve1, total_supply);
+            K_LAST.save(_deps.storage, &reserve0.full_mul(reserve1))?;
         }
+
+        Ok(response
+            .add_attribute("function", "execute_burn_from_vault")
+            .add_attribute("protocol_fee", protocol_fee))
+    }
+
+    /**
+     * Execute Update Fee
+     *
+     * Updates the swap fee of the pool. Only the governance address stored at instantiation
+     * can call it and the new fee must stay within `MAX_FEE_BPS`.
+     */
+    pub fn execute_update_fee(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _fee_bps: u16,
+    ) -> Result<Response, ContractError> {
+        if _fee_bps > MAX_FEE_BPS {
+            return Err(ContractError::InvalidFee { fee_bps: _fee_bps });
+        }
+
+        POOL_CONFIG.update(_deps.storage, |mut config| {
+            if config.governance != _info.sender {
+                return Err(ContractError::Unauthorized {});
+            }
+            config.fee_bps = _fee_bps;
+            Ok(config)
+        })?;
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_update_fee")
+            .add_attribute("fee_bps", _fee_bps.to_string()))
     }
 
 /**
@@ -210,6 +447,11 @@ pub mod execute {
         _info: MessageInfo,
         _msg: MintRecieveParams,
     ) -> Result<Response, ContractError> {
+        // Only the vault of the factory mints, it reports the reserves the liquidity is computed from
+        if vault_address(_deps.as_ref())? != _info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
         let pool_data: Result<PoolDataResponse, _> =
             _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
                 contract_addr: _info.sender.to_string(),
@@ -220,6 +462,14 @@ pub mod execute {
 
         match pool_data {
             Ok(data) => {
+                let (fee_on, protocol_fee) = mint_protocol_fee(
+                    _deps.branch(),
+                    &_env,
+                    _info.sender.as_str(),
+                    data.reserve0,
+                    data.reserve1,
+                )?;
+
                 let total_supply = match TOKEN_INFO.load(_deps.storage) {
                     Ok(data) => data.total_supply,
                     Err(_) => return Err(ContractError::FetchTotalSupplyFailed {}),
@@ -264,21 +514,70 @@ pub mod execute {
                     return Err(ContractError::InsufficientLiquidity {});
                 }
 
+                if let Some(min_liquidity) = _msg.min_liquidity {
+                    if liquidity < min_liquidity {
+                        return Err(ContractError::LiquidityBelowMinimum {
+                            liquidity,
+                            min_liquidity,
+                        });
+                    }
+                }
+
+                if fee_on {
+                    K_LAST.save(
+                        _deps.storage,
+                        &updated_reserve_a.full_mul(updated_reserve_b),
+                    )?;
+                }
+
                 let information = MessageInfo {
                     sender: _env.contract.address.clone(),
                     funds: vec![],
                 };
 
-                match execute_mint(_deps, _env.clone(), information, _msg.to, liquidity) {
-                    Ok(data) => Ok(data
-                        .set_data(to_binary(&packages::vault_msg::ExecutePoolReplyData {
-                            pool_contract_address: _env.contract.address.to_string(),
-                            reserve_a: updated_reserve_a,
-                            reserve_b: updated_reserve_b,
-                        })?)
-                        .add_attribute("minted_amount", liquidity)),
+                // With a hook the LP tokens are minted to the pool first and sent on with cw20 `Send`
+                let recipient = match _msg.on_receive {
+                    Some(_) => _env.contract.address.to_string(),
+                    None => _msg.to.clone(),
+                };
+
+                let mut response = match execute_mint(
+                    _deps.branch(),
+                    _env.clone(),
+                    information.clone(),
+                    recipient,
+                    liquidity,
+                ) {
+                    Ok(response) => response,
                     Err(_) => return Err(ContractError::MintTokenFailed {}),
+                };
+
+                if let Some(on_receive) = _msg.on_receive {
+                    match execute_send(
+                        _deps,
+                        _env.clone(),
+                        information,
+                        _msg.to,
+                        liquidity,
+                        on_receive,
+                    ) {
+                        Ok(send) => {
+                            response = response
+                                .add_submessages(send.messages)
+                                .add_attributes(send.attributes)
+                        }
+                        Err(_) => return Err(ContractError::MintTokenFailed {}),
+                    }
                 }
+
+                Ok(response
+                    .set_data(to_binary(&packages::vault_msg::ExecutePoolReplyData {
+                        pool_contract_address: _env.contract.address.to_string(),
+                        reserve_a: updated_reserve_a,
+                        reserve_b: updated_reserve_b,
+                    })?)
+                    .add_attribute("minted_amount", liquidity)
+                    .add_attribute("protocol_fee", protocol_fee))
             }
             Err(_) => return Err(ContractError::QueryFailed {}),
         }
@@ -303,13 +602,16 @@ pub fn query(_deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Allowance { owner, spender } => {
             to_binary(&query_allowance(_deps, owner, spender)?)
         }
+        QueryMsg::FeeInfo {} => to_binary(&query::query_fee_info(_deps)?),
+        QueryMsg::MarketingInfo {} => to_binary(&query_marketing_info(_deps)?),
+        QueryMsg::DownloadLogo {} => to_binary(&query_download_logo(_deps)?),
     }
 }
 
 pub mod query {
-    use cosmwasm_std::{QueryRequest, WasmQuery};
-    use packages::pool_msg::{GetAmountTokenTransfer, PoolDataResponse};
-    use std::ops::{Add, Div, Mul, Sub};
+    use cosmwasm_std::{QueryRequest, Uint256, WasmQuery};
+    use packages::pool_msg::{FeeInfoResponse, GetAmountTokenTransfer, PoolDataResponse};
+    use std::ops::{Div, Mul, Sub};
 
     use super::*;
 
@@ -331,11 +633,26 @@ pub mod query {
             });
         }
 
-        let numerator = amount_out.mul(Uint128::from(1000u128)).mul(reserve_out);
-        let denominator = reserve_out.sub(amount_out).mul(Uint128::from(997u128));
-        let amount_in = numerator.div(denominator).add(Uint128::from(1u128));
+        if amount_out >= reserve_out {
+            return Err(cosmwasm_std::StdError::GenericErr {
+                msg: "InsufficientLiquidity".to_string(),
+            });
+        }
+
+        let fee_bps = POOL_CONFIG.load(_deps.storage)?.fee_bps;
 
-        Ok(amount_in)
+        // The products are computed in Uint256 so large reserves don't overflow
+        let numerator = reserve_in
+            .full_mul(amount_out)
+            .checked_mul(Uint256::from(FEE_DENOMINATOR))?;
+        let denominator = reserve_out
+            .sub(amount_out)
+            .full_mul(Uint128::from(FEE_DENOMINATOR - fee_bps));
+        let amount_in = numerator
+            .checked_div(denominator)?
+            .checked_add(Uint256::one())?;
+
+        Ok(amount_in.try_into()?)
     }
 
     pub fn query_get_amountout(
@@ -360,12 +677,26 @@ pub mod query {
             });
         }
 
-        let amount_in_with_fee = amount_in.mul(Uint128::from(997u128));
-        let numerator = amount_in_with_fee.mul(reserve_out);
-        let denominator = (reserve_in.mul(Uint128::from(1000u128))).add(amount_in_with_fee);
-        let amountout = numerator.div(denominator);
+        let fee_bps = POOL_CONFIG.load(_deps.storage)?.fee_bps;
+
+        // The products are computed in Uint256 so large reserves don't overflow
+        let amount_in_with_fee = amount_in.full_mul(Uint128::from(FEE_DENOMINATOR - fee_bps));
+        let numerator = amount_in_with_fee.checked_mul(Uint256::from(reserve_out))?;
+        let denominator = reserve_in
+            .full_mul(Uint128::from(FEE_DENOMINATOR))
+            .checked_add(amount_in_with_fee)?;
+        let amountout = numerator.checked_div(denominator)?;
 
-        Ok(amountout)
+        Ok(amountout.try_into()?)
+    }
+
+    pub fn query_fee_info(_deps: Deps) -> StdResult<FeeInfoResponse> {
+        let config = POOL_CONFIG.load(_deps.storage)?;
+
+        Ok(FeeInfoResponse {
+            fee_bps: config.fee_bps,
+            governance: config.governance,
+        })
     }
 
     pub fn get_amount_token_transfer(
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 use serde::{Serialize, Serializer};
 
@@ -7,6 +7,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Cw20(#[from] cw20_base::ContractError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -25,6 +28,12 @@ pub enum ContractError {
     #[error("Liquiity id not sufficient")]
     InsufficientLiquidity {},
 
+    #[error("Minted liquidity {liquidity} is below the minimum {min_liquidity}")]
+    LiquidityBelowMinimum {
+        liquidity: Uint128,
+        min_liquidity: Uint128,
+    },
+
     #[error("Unable to Burn user Lp-Tokens")]
     BurnTokenFailed {},
     
@@ -34,6 +43,15 @@ pub enum ContractError {
     #[error("Unable to query")]
     QueryFailed {},
 
+    #[error(
+        "Invalid fee {fee_bps} bps, the maximum is {} bps",
+        packages::pool_msg::MAX_FEE_BPS
+    )]
+    InvalidFee { fee_bps: u16 },
+
+    #[error("Invalid migration: {val}")]
+    InvalidMigration { val: String },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
 }
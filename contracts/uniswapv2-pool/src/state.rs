@@ -0,0 +1,23 @@
+use cosmwasm_std::Uint256;
+use cw_storage_plus::Item;
+use serde::{Deserialize, Serialize};
+
+/**
+ * PoolConfig: Swap settings of the pool.
+ *
+ * - `governance`: The address allowed to update the fee, the factory that instantiated the pool.
+ * - `fee_bps`: The swap fee charged on the input amount, in basis points.
+ */
+#[derive(Serialize, Deserialize)]
+pub struct PoolConfig {
+    pub governance: String,
+    pub fee_bps: u16,
+}
+
+pub const POOL_CONFIG: Item<PoolConfig> = Item::new("pool_config");
+
+/**
+ * K_LAST: Product of the reserves after the last liquidity event, kept while the protocol fee is on.
+ * The growth of its square root up to the next event is the liquidity earned from swap fees.
+ */
+pub const K_LAST: Item<Uint256> = Item::new("k_last");
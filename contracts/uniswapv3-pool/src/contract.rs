@@ -1,10 +1,14 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult};
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+};
 use cw2::set_contract_version;
 
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{FEE_BPS, GOVERNANCE, TICK_SPACING};
+use packages::pool_msg::{FeeInfoResponse, MAX_FEE_BPS};
 
 const CONTRACT_NAME: &str = "crates.io:uniswapv3-pool";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -18,6 +22,16 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    if _msg.tick_spacing == 0 {
+        return Err(ContractError::CustomError {
+            val: String::from("tick spacing must be greater than zero"),
+        });
+    }
+
+    TICK_SPACING.save(deps.storage, &_msg.tick_spacing)?;
+    FEE_BPS.save(deps.storage, &_msg.fee_bps)?;
+    GOVERNANCE.save(deps.storage, &info.sender.to_string())?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender))
@@ -38,14 +52,46 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
+        ExecuteMsg::UpdateFee { fee_bps } => execute::execute_update_fee(_deps, _info, fee_bps),
+    }
+}
 
+pub mod execute {
+    use super::*;
+
+    /**
+     * Execute Update Fee
+     *
+     * Updates the swap fee of the pool. Only the governance address stored at instantiation
+     * can call it and the new fee must stay within `MAX_FEE_BPS`.
+     */
+    pub fn execute_update_fee(
+        _deps: DepsMut,
+        _info: MessageInfo,
+        _fee_bps: u16,
+    ) -> Result<Response, ContractError> {
+        if _fee_bps > MAX_FEE_BPS {
+            return Err(ContractError::InvalidFee { fee_bps: _fee_bps });
+        }
+
+        if GOVERNANCE.load(_deps.storage)? != _info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+        FEE_BPS.save(_deps.storage, &_fee_bps)?;
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_update_fee")
+            .add_attribute("fee_bps", _fee_bps.to_string()))
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(_deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-
+        QueryMsg::FeeInfo {} => to_binary(&FeeInfoResponse {
+            fee_bps: FEE_BPS.load(_deps.storage)?,
+            governance: GOVERNANCE.load(_deps.storage)?,
+        }),
     }
 }
 
@@ -9,6 +9,12 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error(
+        "Invalid fee {fee_bps} bps, the maximum is {} bps",
+        packages::pool_msg::MAX_FEE_BPS
+    )]
+    InvalidFee { fee_bps: u16 },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
 }
@@ -1,10 +1,19 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use packages::pool_msg::FeeInfoResponse;
 
 #[cw_serde]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    /// spacing between initializable ticks of the pool
+    pub tick_spacing: u32,
+    /// swap fee of the pool in basis points
+    pub fee_bps: u16,
+}
 
 #[cw_serde]
-pub enum ExecuteMsg {}
+pub enum ExecuteMsg {
+    /// Updates the swap fee of the pool, only callable by the pool's governance (its factory)
+    UpdateFee { fee_bps: u16 },
+}
 
 #[cw_serde]
 pub enum MigrateMsg {}
@@ -12,7 +21,6 @@ pub enum MigrateMsg {}
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-    //
-    // #[returns(YourQueryResponse)]
-    // YourQuery {},
+    #[returns(FeeInfoResponse)]
+    FeeInfo {},
 }
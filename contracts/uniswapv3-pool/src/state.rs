@@ -0,0 +1,10 @@
+use cw_storage_plus::Item;
+
+// TICK_SPACING stores the spacing between initializable ticks, set once at instantiation.
+pub const TICK_SPACING: Item<u32> = Item::new("tick_spacing");
+
+// FEE_BPS stores the swap fee of the pool in basis points.
+pub const FEE_BPS: Item<u16> = Item::new("fee_bps");
+
+// GOVERNANCE stores the address allowed to update the fee, the factory that instantiated the pool.
+pub const GOVERNANCE: Item<String> = Item::new("governance");
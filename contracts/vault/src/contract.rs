@@ -1,20 +1,34 @@
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{FACTORY_REGISTER, POOL_REGISTER, VAULT_OWNER};
+use crate::state::{
+    pool_register, FlashLoanState, PriceObservation, WithdrawalState, FACTORY_REGISTER, FEE_TO,
+    FLASH_LOAN, GLOBAL_PAUSE, GUARDIAN, PENDING_DEPOSITS, PENDING_DEPOSIT_TOTALS, POOL_PAUSE,
+    PRICE_OBSERVATIONS, TOKEN_RESERVES, VAULT_OWNER, WITHDRAWAL,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, QueryRequest, Reply, Response,
-    StdError, StdResult, SubMsg, Uint128, WasmMsg, WasmQuery,
+    to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Decimal256, Deps, DepsMut, Empty,
+    Env, Isqrt, MessageInfo, Order, QueryRequest, Reply, Response, StdError, StdResult, Storage,
+    SubMsg, Uint128, Uint256, WasmMsg, WasmQuery,
 };
 use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use packages::asset::{Asset, AssetInfo};
 use packages::vault_msg::{
-    AddLiquidityParams, ContractMsg, Cw20ReceiveMsg, ExecutePoolReplyData, PoolDataResponse,
-    RegisterPoolParams, RemoveLiquidityParams, SwapTokensParams, UpdateLiquidiyParams,
+    AddLiquidityParams, Cw20ReceiveMsg, ExecutePoolReplyData, FactoryRegisteredResponse,
+    FeeToResponse, FlashLoanAsset, FlashLoanReceiverMsg, MultiHopSwapParams, PauseStatus,
+    PoolDataResponse, ProvidePendingParams, ProvideSingleSidedParams, RegisterPoolParams,
+    SwapExactOutParams, SwapTokensParams, UpdateLiquidiyParams, VaultHookMsg,
+    WithdrawLiquidityParams, MAX_SWAP_HOPS, MAX_TWAP_WINDOW,
 };
 
 const CONTRACT_NAME: &str = "crates.io:vault";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+// Most expired price observations of a pool deleted per reserve change, so pruning never runs out of gas
+const MAX_PRUNED_OBSERVATIONS: usize = 10;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -40,6 +54,11 @@ pub fn execute(
     _info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Flash loans are settled on the balances of the vault, nothing else can move them while a loan is open
+    if FLASH_LOAN.may_load(_deps.storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+
     match msg {
         ExecuteMsg::RegisterFactory { factory_address } => {
             execute::execute_register_factory(_deps, _env, _info, factory_address)
@@ -50,42 +69,424 @@ pub fn execute(
         ExecuteMsg::AddLiquidity(add_liquidity_params) => {
             execute::execute_add_liquidity(_deps, _env, _info, add_liquidity_params)
         }
-        ExecuteMsg::RemoveLiquidity(remove_liquidity_params) => {
-            execute::execute_remove_liquidity(_deps, _env, _info, remove_liquidity_params)
-        }
         ExecuteMsg::Receive(cw_receive_msg) => {
-            execute::execute_swap_tokens(_deps, _env, _info, cw_receive_msg)
+            execute::execute_receive(_deps, _env, _info, cw_receive_msg)
+        }
+        ExecuteMsg::Swap(swap_token_params) => {
+            execute::execute_swap_native_tokens(_deps, _env, _info, swap_token_params)
+        }
+        ExecuteMsg::SetGuardian { guardian } => {
+            execute::execute_set_guardian(_deps, _env, _info, guardian)
+        }
+        ExecuteMsg::SetPause {
+            pool_address,
+            status,
+        } => execute::execute_set_pause(_deps, _env, _info, pool_address, status),
+        ExecuteMsg::MultiHopSwap(multi_hop_swap_params) => {
+            execute::execute_multi_hop_swap(_deps, _env, _info, multi_hop_swap_params)
+        }
+        ExecuteMsg::SwapExactOut(swap_exact_out_params) => {
+            execute::execute_swap_exact_out(_deps, _env, _info, swap_exact_out_params)
+        }
+        ExecuteMsg::FlashLoan {
+            assets,
+            recipient,
+            msg,
+        } => execute::execute_flash_loan(_deps, _env, _info, assets, recipient, msg),
+        ExecuteMsg::SetFeeTo { fee_to } => execute::execute_set_fee_to(_deps, _env, _info, fee_to),
+        ExecuteMsg::Sync { pool_address } => {
+            execute::execute_sync(_deps, _env, _info, pool_address)
+        }
+        ExecuteMsg::Skim { pool_address, to } => {
+            execute::execute_skim(_deps, _env, _info, pool_address, to)
+        }
+        ExecuteMsg::ProvidePending(provide_pending_params) => {
+            execute::execute_provide_pending(_deps, _env, _info, provide_pending_params)
+        }
+        ExecuteMsg::WithdrawPending { pool_address } => {
+            execute::execute_withdraw_pending(_deps, _info, pool_address)
+        }
+        ExecuteMsg::ProvideSingleSided(provide_single_sided_params) => {
+            execute::execute_provide_single_sided(_deps, _env, _info, provide_single_sided_params)
+        }
+    }
+}
+
+/**
+ * pause_status: Returns what is halted for `_pool_address`, that is what is halted either globally
+ * or for the pool itself.
+ */
+pub fn pause_status(_storage: &dyn Storage, _pool_address: &str) -> StdResult<PauseStatus> {
+    let global = GLOBAL_PAUSE.may_load(_storage)?.unwrap_or_default();
+    let pool = POOL_PAUSE
+        .may_load(_storage, _pool_address.to_string())?
+        .unwrap_or_default();
+
+    Ok(PauseStatus {
+        swaps: global.swaps || pool.swaps,
+        deposits: global.deposits || pool.deposits,
+    })
+}
+
+/**
+ * check_deadline: Fails with `Expired` when `_deadline`, in unix seconds, is earlier than the block time.
+ */
+pub fn check_deadline(_env: &Env, _deadline: Uint128) -> Result<(), ContractError> {
+    let block_time = _env.block.time.seconds();
+
+    if _deadline < Uint128::from(block_time) {
+        return Err(ContractError::Expired {
+            deadline: _deadline,
+            block_time,
+        });
+    }
+
+    Ok(())
+}
+
+/**
+ * price_impact: Returns how far the price of a swap of `_amount_in` for `_amount_out` is from the spot price
+ * of the pool, `_reserve_out / _reserve_in`, as a fraction of the spot output. The swap fee is part of it.
+ */
+pub fn price_impact(
+    _amount_in: Uint128,
+    _amount_out: Uint128,
+    _reserve_in: Uint128,
+    _reserve_out: Uint128,
+) -> Decimal {
+    if _reserve_in.is_zero() {
+        return Decimal::one();
+    }
+
+    let spot_amount_out = _amount_in.multiply_ratio(_reserve_out, _reserve_in);
+    if spot_amount_out.is_zero() {
+        return Decimal::one();
+    }
+
+    Decimal::one()
+        .checked_sub(Decimal::from_ratio(_amount_out, spot_amount_out))
+        .unwrap_or_default()
+}
+
+/**
+ * token_reserves: Returns the sum of the reserves of every registered pool in `_token`, leaving out the
+ * pool `_excluded_pool` when given.
+ */
+pub fn token_reserves(
+    _deps: Deps,
+    _token: &AssetInfo,
+    _excluded_pool: Option<&str>,
+) -> StdResult<Uint128> {
+    let reserves = TOKEN_RESERVES
+        .may_load(_deps.storage, _token.as_str())?
+        .unwrap_or_default();
+
+    let excluded = match _excluded_pool {
+        Some(pool_address) => {
+            match pool_register().may_load(_deps.storage, pool_address.to_string())? {
+                Some(data) if &data.token0 == _token => data.reserve0,
+                Some(data) if &data.token1 == _token => data.reserve1,
+                _ => Uint128::zero(),
+            }
         }
+        None => Uint128::zero(),
+    };
+
+    Ok(reserves.checked_sub(excluded)?)
+}
+
+/**
+ * update_token_reserves: Replaces the `_previous` reserve of a pool in `_token` by its `_current` one in the
+ * reserves of the vault in `_token`.
+ */
+fn update_token_reserves(
+    _storage: &mut dyn Storage,
+    _token: &AssetInfo,
+    _previous: Uint128,
+    _current: Uint128,
+) -> StdResult<()> {
+    TOKEN_RESERVES.update(_storage, _token.as_str(), |total| -> StdResult<Uint128> {
+        Ok(total
+            .unwrap_or_default()
+            .checked_sub(_previous)?
+            .checked_add(_current)?)
+    })?;
+
+    Ok(())
+}
+
+/**
+ * zap_swap_amount: Returns the part of `_amount_in` to swap through a constant product pool holding `_reserve_in`
+ * of it, so that the rest of `_amount_in` and the swap output match the price of the pool after the swap.
+ *
+ * With F the fee denominator and g = F - `_fee_bps`, it is the positive root of
+ * g * s^2 + (F + g) * R * s - F * A * R = 0 for the amount in A and the reserve in R.
+ */
+pub fn zap_swap_amount(
+    _amount_in: Uint128,
+    _reserve_in: Uint128,
+    _fee_bps: u16,
+) -> StdResult<Uint128> {
+    let denominator = Uint256::from(packages::pool_msg::FEE_DENOMINATOR);
+    let fee_complement = Uint256::from(packages::pool_msg::FEE_DENOMINATOR - _fee_bps);
+    let reserve_in = Uint256::from(_reserve_in);
+
+    let b = (denominator + fee_complement).checked_mul(reserve_in)?;
+    let discriminant = b.checked_mul(b)?.checked_add(
+        Uint256::from(4u8)
+            .checked_mul(fee_complement)?
+            .checked_mul(denominator)?
+            .checked_mul(Uint256::from(_amount_in))?
+            .checked_mul(reserve_in)?,
+    )?;
+
+    let swap_amount = (discriminant.isqrt() - b) / (Uint256::from(2u8) * fee_complement);
+
+    Ok(swap_amount.try_into()?)
+}
+
+/**
+ * pending_deposits: Returns the sum of the pending deposits in `_token`, held by the vault outside of the
+ * reserves of the pools.
+ */
+pub fn pending_deposits(_deps: Deps, _token: &AssetInfo) -> StdResult<Uint128> {
+    Ok(PENDING_DEPOSIT_TOTALS
+        .may_load(_deps.storage, _token.as_str())?
+        .unwrap_or_default())
+}
+
+/**
+ * release_pending_deposit: Removes the pending deposit `_key` and takes its assets out of the pending totals,
+ * returning them.
+ */
+pub fn release_pending_deposit(
+    _storage: &mut dyn Storage,
+    _key: (String, String),
+) -> StdResult<Option<Vec<Asset>>> {
+    let pending = match PENDING_DEPOSITS.may_load(_storage, _key.clone())? {
+        Some(pending) => pending,
+        None => return Ok(None),
+    };
+    PENDING_DEPOSITS.remove(_storage, _key);
+
+    for asset in pending.iter() {
+        PENDING_DEPOSIT_TOTALS.update(
+            _storage,
+            asset.info.as_str(),
+            |total| -> StdResult<Uint128> {
+                Ok(total.unwrap_or_default().checked_sub(asset.amount)?)
+            },
+        )?;
+    }
+
+    Ok(Some(pending))
+}
+
+/**
+ * pool_reserves: Returns the reserves in and out of the registered pool `_pool_address` for a swap
+ * from `_token_in` to `_token_out`, which must be the two tokens of the pool.
+ */
+pub fn pool_reserves(
+    _deps: Deps,
+    _pool_address: &str,
+    _token_in: &AssetInfo,
+    _token_out: &AssetInfo,
+) -> Result<(PoolDataResponse, Uint128, Uint128), ContractError> {
+    let data = match pool_register().load(_deps.storage, _pool_address.to_string()) {
+        Ok(data) => data,
+        Err(_) => return Err(ContractError::PoolNotExisted {}),
+    };
+
+    // Swaps go from one token of the pool to the other one
+    let (reserve_in, reserve_out) = if _token_in == &data.token0 && _token_out == &data.token1 {
+        (data.reserve0, data.reserve1)
+    } else if _token_in == &data.token1 && _token_out == &data.token0 {
+        (data.reserve1, data.reserve0)
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    };
+
+    Ok((data, reserve_in, reserve_out))
+}
+
+/**
+ * price_increments: Returns the price of token0 in token1 and of token1 in token0 for the reserves
+ * `_reserve0` and `_reserve1`, times the `_elapsed` seconds they held. Empty reserves have no price.
+ */
+pub fn price_increments(
+    _reserve0: Uint128,
+    _reserve1: Uint128,
+    _elapsed: u64,
+) -> (Decimal256, Decimal256) {
+    if _elapsed == 0 || _reserve0.is_zero() || _reserve1.is_zero() {
+        return (Decimal256::zero(), Decimal256::zero());
+    }
+
+    (
+        Decimal256::from_ratio(
+            Uint256::from(_reserve1) * Uint256::from(_elapsed),
+            _reserve0,
+        ),
+        Decimal256::from_ratio(
+            Uint256::from(_reserve0) * Uint256::from(_elapsed),
+            _reserve1,
+        ),
+    )
+}
+
+/**
+ * accumulate_prices: Adds the prices of the pool times the seconds they held since its last reserve change
+ * to its price accumulators, the same way Uniswap V2 does.
+ */
+pub fn accumulate_prices(_pool: &mut PoolDataResponse, _block_time: u64) {
+    let (price0, price1) = price_increments(
+        _pool.reserve0,
+        _pool.reserve1,
+        _block_time.saturating_sub(_pool.block_time_last),
+    );
+
+    _pool.price0_cumulative_last += price0;
+    _pool.price1_cumulative_last += price1;
+    _pool.block_time_last = _block_time;
+}
+
+/**
+ * prune_price_observations: Deletes the oldest observations of the pool `_pool_address` that are older than
+ * `MAX_TWAP_WINDOW` at `_block_time`, at most `MAX_PRUNED_OBSERVATIONS` of them. The last of them is kept,
+ * the price accumulators at the start of the window are read from it.
+ */
+fn prune_price_observations(
+    _storage: &mut dyn Storage,
+    _pool_address: &str,
+    _block_time: u64,
+) -> StdResult<()> {
+    let cutoff = _block_time.saturating_sub(MAX_TWAP_WINDOW);
+    let expired = PRICE_OBSERVATIONS
+        .prefix(_pool_address.to_string())
+        .keys(
+            _storage,
+            None,
+            Some(Bound::inclusive(cutoff)),
+            Order::Ascending,
+        )
+        .take(MAX_PRUNED_OBSERVATIONS + 1)
+        .collect::<StdResult<Vec<u64>>>()?;
+
+    for observed_at in expired.iter().take(expired.len().saturating_sub(1)) {
+        PRICE_OBSERVATIONS.remove(_storage, (_pool_address.to_string(), *observed_at));
     }
+
+    Ok(())
+}
+
+/**
+ * price_cumulatives_at: Returns the price accumulators of the pool `_pool_address` at `_time`, from its last
+ * observation at or before it and the reserves it left.
+ */
+pub fn price_cumulatives_at(
+    _deps: Deps,
+    _pool_address: &str,
+    _time: u64,
+) -> StdResult<(Decimal256, Decimal256)> {
+    let observation = PRICE_OBSERVATIONS
+        .prefix(_pool_address.to_string())
+        .range(
+            _deps.storage,
+            None,
+            Some(Bound::inclusive(_time)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?;
+
+    let (observed_at, observation) = match observation {
+        Some(observation) => observation,
+        None => {
+            return Err(StdError::GenericErr {
+                msg: format!("No price observation at or before {}", _time),
+            })
+        }
+    };
+
+    let (price0, price1) = price_increments(
+        observation.reserve0,
+        observation.reserve1,
+        _time - observed_at,
+    );
+
+    Ok((
+        observation.price0_cumulative + price0,
+        observation.price1_cumulative + price1,
+    ))
 }
 
 pub mod execute {
     use cosmwasm_std::from_binary;
 
     use super::*;
-    use std::ops::{AddAssign, SubAssign};
 
     /**
      * Internal Functions
      *
-     * 1. execute_wasm_execute: This function generates multiple `Execute` messages to interact with other CosmWasm contracts.
+     * 1. collect_asset: This function generates the messages depositing `_amount` of an asset into the vault.
      *
-     * @param _contract_msg A vector of `ContractMsg` objects, each containing information about the target contract
-     * address and the binary message to be sent to that contract.
+     * cw20 tokens are pulled from the sender with `TransferFrom`. Native tokens must be attached to the
+     * message for exactly `_amount_desired`, the part of it that isn't deposited is refunded to the sender.
      *
-     * @returns A vector of `WasmMsg` containing the generated `Execute` messages.
+     * @returns A vector of `CosmosMsg` to be executed before the deposit is accounted.
      */
-    fn execute_wasm_execute(_contract_msg: Vec<ContractMsg>) -> Vec<WasmMsg> {
-        let execute_messages = _contract_msg
-            .iter()
-            .map(|contract_msg| WasmMsg::Execute {
-                contract_addr: contract_msg.contract_address.clone(),
-                msg: contract_msg.contract_msg.clone(),
-                funds: vec![],
-            })
-            .collect();
+    fn collect_asset(
+        _env: &Env,
+        _info: &MessageInfo,
+        _asset: &AssetInfo,
+        _amount_desired: Uint128,
+        _amount: Uint128,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
+        match _asset {
+            AssetInfo::Cw20 { .. } => Ok(vec![_asset.transfer_from_msg(
+                _info.sender.as_str(),
+                _env.contract.address.as_str(),
+                _amount,
+            )?]),
+            AssetInfo::Native { denom } => {
+                if _asset.sent_amount(_info) != _amount_desired {
+                    return Err(ContractError::InvalidFunds {
+                        val: format!("expected {}{}", _amount_desired, denom),
+                    });
+                }
+
+                if _amount_desired > _amount {
+                    Ok(vec![BankMsg::Send {
+                        to_address: _info.sender.to_string(),
+                        amount: vec![Coin {
+                            denom: denom.clone(),
+                            amount: _amount_desired - _amount,
+                        }],
+                    }
+                    .into()])
+                } else {
+                    Ok(vec![])
+                }
+            }
+        }
+    }
+
+    /// Checks that every coin attached to the message is one of `_assets`
+    fn assert_funds_of(_info: &MessageInfo, _assets: &[&AssetInfo]) -> Result<(), ContractError> {
+        for coin in &_info.funds {
+            let expected = _assets.iter().any(|asset| match asset {
+                AssetInfo::Native { denom } => denom == &coin.denom,
+                AssetInfo::Cw20 { .. } => false,
+            });
+
+            if !expected {
+                return Err(ContractError::InvalidFunds {
+                    val: format!("unexpected {}", coin),
+                });
+            }
+        }
 
-        execute_messages
+        Ok(())
     }
 
     /**
@@ -168,7 +569,8 @@ pub mod execute {
         _params: AddLiquidityParams,
         mut _reserve_a: Uint128,
         mut _reserve_b: Uint128,
-    ) -> Result<Vec<WasmMsg>, ContractError> {
+        _escrowed: bool,
+    ) -> Result<Vec<CosmosMsg>, ContractError> {
         let (_amount_a, _amount_b) =
             if _reserve_a == Uint128::zero() && _reserve_b == Uint128::zero() {
                 (_params.amount_a_desired, _params.amount_b_desired)
@@ -186,34 +588,53 @@ pub mod execute {
                 }
             };
 
-        let execute_messages = execute_wasm_execute(vec![
-            ContractMsg {
-                contract_address: _params.token_a,
-                contract_msg: to_binary(&cw20_base::msg::ExecuteMsg::TransferFrom {
-                    owner: _info.sender.to_string(),
-                    recipient: _env.contract.address.to_string(),
-                    amount: _amount_a,
-                })?,
-            },
-            ContractMsg {
-                contract_address: _params.token_b,
-                contract_msg: to_binary(&cw20_base::msg::ExecuteMsg::TransferFrom {
-                    owner: _info.sender.to_string(),
-                    recipient: _env.contract.address.to_string(),
-                    amount: _amount_b,
-                })?,
-            },
-            ContractMsg {
-                contract_address: _params.pool_address,
-                contract_msg: to_binary(&packages::pool_msg::PoolExecuteMsg::Mint(
+        let mut execute_messages = vec![];
+        if _escrowed {
+            // Escrowed tokens are held by the vault already, the part of them that isn't deposited is refunded
+            for (token, amount_desired, amount) in [
+                (&_params.token_a, _params.amount_a_desired, _amount_a),
+                (&_params.token_b, _params.amount_b_desired, _amount_b),
+            ] {
+                if amount_desired > amount {
+                    execute_messages
+                        .push(token.transfer_msg(_info.sender.as_str(), amount_desired - amount)?);
+                }
+            }
+        } else {
+            assert_funds_of(&_info, &[&_params.token_a, &_params.token_b])?;
+
+            execute_messages.extend(collect_asset(
+                &_env,
+                &_info,
+                &_params.token_a,
+                _params.amount_a_desired,
+                _amount_a,
+            )?);
+            execute_messages.extend(collect_asset(
+                &_env,
+                &_info,
+                &_params.token_b,
+                _params.amount_b_desired,
+                _amount_b,
+            )?);
+        }
+
+        execute_messages.push(
+            WasmMsg::Execute {
+                contract_addr: _params.pool_address,
+                msg: to_binary(&packages::pool_msg::PoolExecuteMsg::Mint(
                     packages::pool_msg::MintRecieveParams {
-                        to: _info.sender.to_string(),
+                        to: _deps.api.addr_validate(&_params.address_to)?.to_string(),
                         amount0: _amount_a,
                         amount1: _amount_b,
+                        on_receive: _params.on_receive,
+                        min_liquidity: _params.min_liquidity,
                     },
                 ))?,
-            },
-        ]);
+                funds: vec![],
+            }
+            .into(),
+        );
 
         Ok(execute_messages)
     }
@@ -279,10 +700,13 @@ pub mod execute {
                         token1: _register_pool_params.token1,
                         reserve0: Uint128::zero(),
                         reserve1: Uint128::zero(),
+                        price0_cumulative_last: Decimal256::zero(),
+                        price1_cumulative_last: Decimal256::zero(),
+                        block_time_last: _env.block.time.seconds(),
                     };
 
-                    // Save the pool registration data in the `POOL_REGISTER` mapping
-                    POOL_REGISTER.save(
+                    // Save the pool registration data in the `pool_register` mapping
+                    pool_register().save(
                         _deps.storage,
                         _register_pool_params.pool_address.clone(),
                         &pool_data,
@@ -308,12 +732,45 @@ pub mod execute {
         _env: Env,
         _info: MessageInfo,
         _add_liquidity_params: AddLiquidityParams,
+    ) -> Result<Response, ContractError> {
+        add_liquidity(_deps, _env, _info, _add_liquidity_params, false)
+    }
+
+    /**
+     * add_liquidity: Deposits the tokens of `_add_liquidity_params` into the pool and mints the LP tokens,
+     * the tokens are collected from the sender unless they are `_escrowed` by the vault for it already.
+     */
+    fn add_liquidity(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _add_liquidity_params: AddLiquidityParams,
+        _escrowed: bool,
     ) -> Result<Response, ContractError> {
         let fetch_pool_data =
-            POOL_REGISTER.load(_deps.storage, _add_liquidity_params.pool_address.clone());
+            pool_register().load(_deps.storage, _add_liquidity_params.pool_address.clone());
+
+        check_deadline(&_env, _add_liquidity_params.deadline)?;
+
+        if pause_status(_deps.storage, &_add_liquidity_params.pool_address)?.deposits {
+            return Err(ContractError::Paused {
+                action: String::from("Deposits"),
+            });
+        }
 
         match fetch_pool_data {
             Ok(data) => {
+                // The deposited tokens must be the tokens of the pool, in any order
+                let (token_a, token_b) = (
+                    &_add_liquidity_params.token_a,
+                    &_add_liquidity_params.token_b,
+                );
+                if !((token_a == &data.token0 && token_b == &data.token1)
+                    || (token_a == &data.token1 && token_b == &data.token0))
+                {
+                    return Err(ContractError::AssetMismatch {});
+                }
+
                 let _params = if data.token0 == _add_liquidity_params.token_a {
                     AddLiquidityParams {
                         pool_address: _add_liquidity_params.pool_address,
@@ -325,6 +782,8 @@ pub mod execute {
                         amount_b_min: _add_liquidity_params.amount_b_min,
                         address_to: _add_liquidity_params.address_to,
                         deadline: _add_liquidity_params.deadline,
+                        on_receive: _add_liquidity_params.on_receive,
+                        min_liquidity: _add_liquidity_params.min_liquidity,
                     }
                 } else {
                     AddLiquidityParams {
@@ -337,6 +796,8 @@ pub mod execute {
                         amount_b_min: _add_liquidity_params.amount_a_min,
                         address_to: _add_liquidity_params.address_to,
                         deadline: _add_liquidity_params.deadline,
+                        on_receive: _add_liquidity_params.on_receive,
+                        min_liquidity: _add_liquidity_params.min_liquidity,
                     }
                 };
 
@@ -347,6 +808,7 @@ pub mod execute {
                     _params,
                     data.reserve0,
                     data.reserve1,
+                    _escrowed,
                 ) {
                     Ok(messages) => messages,
                     Err(err) => return Err(err),
@@ -375,223 +837,1670 @@ pub mod execute {
         }
     }
 
+    /// Adds `_asset` to the pending deposit of `_depositor` into the pool `_pool_address`
+    fn add_pending_deposit(
+        _deps: DepsMut,
+        _depositor: &str,
+        _pool_address: &str,
+        _asset: Asset,
+    ) -> Result<(), ContractError> {
+        let data = match pool_register().load(_deps.storage, _pool_address.to_string()) {
+            Ok(data) => data,
+            Err(_) => return Err(ContractError::PoolNotExisted {}),
+        };
+
+        if _asset.info != data.token0 && _asset.info != data.token1 {
+            return Err(ContractError::AssetMismatch {});
+        }
+
+        if pause_status(_deps.storage, _pool_address)?.deposits {
+            return Err(ContractError::Paused {
+                action: String::from("Deposits"),
+            });
+        }
+
+        PENDING_DEPOSIT_TOTALS.update(
+            _deps.storage,
+            _asset.info.as_str(),
+            |total| -> StdResult<Uint128> { Ok(total.unwrap_or_default() + _asset.amount) },
+        )?;
+        PENDING_DEPOSITS.update(
+            _deps.storage,
+            (_depositor.to_string(), _pool_address.to_string()),
+            |pending| -> StdResult<Vec<Asset>> {
+                let mut pending = pending.unwrap_or_default();
+                match pending.iter_mut().find(|asset| asset.info == _asset.info) {
+                    Some(asset) => asset.amount += _asset.amount,
+                    None => pending.push(_asset),
+                }
+                Ok(pending)
+            },
+        )?;
+
+        Ok(())
+    }
+
     /**
-     * 3. execute_remove_liquidity: This function allows users to remove liquidity from a pool by specifying
-     * the pool address, the tokens they want to withdraw, the minimum acceptable amounts of each token, the recipient's
-     * address for receiving the tokens, and a deadline for the operation.
+     * execute_deposit_pending: Escrows the cw20 tokens sent to the vault into the pending deposit of
+     * `_depositor`, to be provided as liquidity once the other token of the pool joins it.
      */
-    pub fn execute_remove_liquidity(
+    pub fn execute_deposit_pending(
         _deps: DepsMut,
+        _depositor: String,
+        _offer_asset: Asset,
+        _pool_address: String,
+    ) -> Result<Response, ContractError> {
+        add_pending_deposit(_deps, &_depositor, &_pool_address, _offer_asset.clone())?;
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_deposit_pending")
+            .add_attribute("depositor", _depositor)
+            .add_attribute("pool_address", _pool_address)
+            .add_attribute("deposited", _offer_asset.to_string()))
+    }
+
+    /**
+     * execute_provide_pending: Adds the native tokens attached to the message to the pending deposit of the
+     * sender, and provides all of it as liquidity.
+     */
+    pub fn execute_provide_pending(
+        mut _deps: DepsMut,
         _env: Env,
         _info: MessageInfo,
-        _remove_liquidity_params: RemoveLiquidityParams,
+        _provide_pending_params: ProvidePendingParams,
     ) -> Result<Response, ContractError> {
-        match execute_update_liquidity(
-            _deps,
-            _env,
-            UpdateLiquidiyParams {
-                pool_address: _info.sender.to_string(),
-                amount_a: _remove_liquidity_params.reserve_a,
-                amount_b: _remove_liquidity_params.reserve_b,
-            },
-        ) {
-            Ok(data) => {
-                let _execute_messages = execute_wasm_execute(vec![
-                    ContractMsg {
-                        contract_address: _remove_liquidity_params.token_a,
-                        contract_msg: to_binary(&cw20_base::msg::ExecuteMsg::Transfer {
-                            recipient: _remove_liquidity_params.address_to.clone(),
-                            amount: _remove_liquidity_params.amount_a,
-                        })?,
+        for coin in &_info.funds {
+            add_pending_deposit(
+                _deps.branch(),
+                _info.sender.as_str(),
+                &_provide_pending_params.pool_address,
+                Asset {
+                    info: AssetInfo::Native {
+                        denom: coin.denom.clone(),
                     },
-                    ContractMsg {
-                        contract_address: _remove_liquidity_params.token_b,
-                        contract_msg: to_binary(&cw20_base::msg::ExecuteMsg::Transfer {
-                            recipient: _remove_liquidity_params.address_to,
-                            amount: _remove_liquidity_params.amount_b,
-                        })?,
-                    },
-                ]);
-
-                Ok(data.add_messages(_execute_messages))
-            }
-            Err(_) => {
-                return Err(ContractError::CustomError {
-                    val: String::from("Update Reserve failed"),
-                })
-            }
+                    amount: coin.amount,
+                },
+            )?;
         }
+
+        provide_pending(_deps, _env, _info.sender, _provide_pending_params)
     }
 
-    pub fn execute_update_liquidity(
+    /// Provides the whole pending deposit of `_depositor` into the pool as liquidity
+    fn provide_pending(
         _deps: DepsMut,
         _env: Env,
-        _update_liquidity_params: UpdateLiquidiyParams,
+        _depositor: Addr,
+        _provide_pending_params: ProvidePendingParams,
     ) -> Result<Response, ContractError> {
-        let _update_pool_register = POOL_REGISTER.update(
-            _deps.storage,
-            _update_liquidity_params.pool_address,
-            |pool_data| -> Result<PoolDataResponse, ContractError> {
-                match pool_data {
-                    Some(mut pool) => {
-                        pool.reserve0 = _update_liquidity_params.amount_a;
-                        pool.reserve1 = _update_liquidity_params.amount_b;
-                        Ok(pool)
-                    }
-                    None => return Err(ContractError::PoolNotExisted {}),
-                }
-            },
+        let data = match pool_register()
+            .load(_deps.storage, _provide_pending_params.pool_address.clone())
+        {
+            Ok(data) => data,
+            Err(_) => return Err(ContractError::PoolNotExisted {}),
+        };
+
+        let key = (
+            _depositor.to_string(),
+            _provide_pending_params.pool_address.clone(),
         );
+        let pending = PENDING_DEPOSITS
+            .may_load(_deps.storage, key.clone())?
+            .unwrap_or_default();
+        let pending_amount = |token: &AssetInfo| {
+            pending
+                .iter()
+                .find(|asset| &asset.info == token)
+                .map(|asset| asset.amount)
+                .unwrap_or_default()
+        };
+        let (amount0, amount1) = (pending_amount(&data.token0), pending_amount(&data.token1));
 
-        match _update_pool_register {
-            Ok(_) => Ok(Response::new().add_attribute("function", "execute_update_liquidity")),
-            Err(_) => return Err(ContractError::UpateLiquidityFailed {}),
+        if amount0.is_zero() || amount1.is_zero() {
+            return Err(ContractError::InsufficientAmount {});
         }
+
+        release_pending_deposit(_deps.storage, key)?;
+
+        let response = add_liquidity(
+            _deps,
+            _env,
+            MessageInfo {
+                sender: _depositor,
+                funds: vec![],
+            },
+            AddLiquidityParams {
+                pool_address: _provide_pending_params.pool_address.clone(),
+                token_a: data.token0,
+                token_b: data.token1,
+                amount_a_desired: amount0,
+                amount_b_desired: amount1,
+                amount_a_min: _provide_pending_params.amount0_min,
+                amount_b_min: _provide_pending_params.amount1_min,
+                address_to: _provide_pending_params.address_to,
+                deadline: _provide_pending_params.deadline,
+                on_receive: _provide_pending_params.on_receive,
+                min_liquidity: None,
+            },
+            true,
+        )?;
+
+        Ok(response
+            .add_attribute("function", "execute_provide_pending")
+            .add_attribute("pool_address", _provide_pending_params.pool_address))
     }
 
-    pub fn execute_swap_tokens(
+    /**
+     * execute_provide_single_sided: Provides liquidity from the native token attached to the message alone.
+     */
+    pub fn execute_provide_single_sided(
         _deps: DepsMut,
         _env: Env,
         _info: MessageInfo,
-        _cw20_receive_msg: Cw20ReceiveMsg,
+        _provide_single_sided_params: ProvideSingleSidedParams,
     ) -> Result<Response, ContractError> {
-        let message = String::from("execute_swap_tokens");
-        let _swap_token_params: SwapTokensParams = from_binary(&_cw20_receive_msg.msg)?;
+        let offer_asset = native_offer_asset(&_info, &_provide_single_sided_params.token_in)?;
 
-        if message != _swap_token_params.message {
-            return Err(ContractError::SwapFailed {});
-        }
+        provide_single_sided(
+            _deps,
+            _env,
+            _info.sender,
+            offer_asset,
+            _provide_single_sided_params,
+        )
+    }
 
-        let pool_exist = POOL_REGISTER.load(_deps.storage, _swap_token_params.pool_address.clone());
+    /**
+     * provide_single_sided: Swaps the part of `_offer_asset` given by `zap_swap_amount` for the other token
+     * of the pool through the pool itself, then provides the rest of it and the swap output as liquidity
+     * for `_depositor`. The dust left by the rounding of the swap is refunded.
+     */
+    fn provide_single_sided(
+        mut _deps: DepsMut,
+        _env: Env,
+        _depositor: Addr,
+        _offer_asset: Asset,
+        _params: ProvideSingleSidedParams,
+    ) -> Result<Response, ContractError> {
+        check_deadline(&_env, _params.deadline)?;
 
-        match pool_exist {
-            Ok(data) => {
-                let mut updated_amount_a = data.reserve0;
-                let mut updated_amount_b = data.reserve1;
-
-                let _amount_out = if _swap_token_params.token_in == data.token0 {
-                    let amount_out: Result<Uint128, _> =
-                        _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-                            contract_addr: _swap_token_params.pool_address.clone(),
-                            msg: to_binary(&packages::pool_msg::PoolQueryMsg::GetAmountOut(
-                                packages::pool_msg::AmountOutParams {
-                                    amount_in: _cw20_receive_msg.amount,
-                                    reserve_in: data.reserve0,
-                                    reserve_out: data.reserve1,
-                                },
-                            ))?,
-                        }));
-
-                    match amount_out {
-                        Ok(_amount_out) => {
-                            updated_amount_a.add_assign(_cw20_receive_msg.amount);
-                            updated_amount_b.sub_assign(_amount_out);
-                            _amount_out
-                        }
-                        Err(_) => {
-                            return Err(ContractError::CustomError {
-                                val: "Unable to find amount out".to_string(),
-                            })
-                        }
-                    }
-                } else {
-                    let amount_out: Result<Uint128, _> =
-                        _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-                            contract_addr: _swap_token_params.pool_address.clone(),
-                            msg: to_binary(&packages::pool_msg::PoolQueryMsg::GetAmountOut(
-                                packages::pool_msg::AmountOutParams {
-                                    amount_in: _cw20_receive_msg.amount,
-                                    reserve_in: data.reserve1,
-                                    reserve_out: data.reserve0,
-                                },
-                            ))?,
-                        }));
-
-                    match amount_out {
-                        Ok(_amount_out) => {
-                            updated_amount_a.sub_assign(_amount_out);
-                            updated_amount_b.add_assign(_cw20_receive_msg.amount);
-                            _amount_out
-                        }
-                        Err(_) => {
-                            return Err(ContractError::CustomError {
-                                val: "Unable to find amount out".to_string(),
-                            })
-                        }
-                    }
-                };
+        if _offer_asset.info != _params.token_in {
+            return Err(ContractError::AssetMismatch {});
+        }
 
-                match execute_update_liquidity(
-                    _deps,
-                    _env.clone(),
-                    UpdateLiquidiyParams {
-                        pool_address: _swap_token_params.pool_address,
-                        amount_a: updated_amount_a,
-                        amount_b: updated_amount_b,
-                    },
-                ) {
-                    Ok(response) => {
-                        let execute_message = WasmMsg::Execute {
-                            contract_addr: _swap_token_params.token_out,
-                            msg: to_binary(&cw20_base::msg::ExecuteMsg::Transfer {
-                                recipient: _swap_token_params.address_to,
-                                amount: _amount_out,
-                            })?,
-                            funds: vec![],
-                        };
+        let data = match pool_register().load(_deps.storage, _params.pool_address.clone()) {
+            Ok(data) => data,
+            Err(_) => return Err(ContractError::PoolNotExisted {}),
+        };
+        let token_out = if _offer_asset.info == data.token0 {
+            data.token1
+        } else if _offer_asset.info == data.token1 {
+            data.token0
+        } else {
+            return Err(ContractError::AssetMismatch {});
+        };
 
-                        Ok(response.add_message(execute_message))
-                    }
-                    Err(_) => {
-                        return Err(ContractError::CustomError {
-                            val: String::from("Update Reserve failed"),
-                        })
-                    }
-                }
-            }
-            Err(_) => {
-                return Err(ContractError::PoolNotExisted {});
-            }
+        let (_, reserve_in, reserve_out) = pool_reserves(
+            _deps.as_ref(),
+            &_params.pool_address,
+            &_offer_asset.info,
+            &token_out,
+        )?;
+        if reserve_in.is_zero() || reserve_out.is_zero() {
+            return Err(ContractError::InsufficientLiquidity {});
         }
-    }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(_deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::QueryPoolData { pool_address } => {
-            to_binary(&query::query_pool_data(_deps, _env, pool_address)?)
-        }
-    }
-}
+        let fee_info: packages::pool_msg::FeeInfoResponse =
+            _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: _params.pool_address.clone(),
+                msg: to_binary(&packages::pool_msg::PoolQueryMsg::FeeInfo {})?,
+            }))?;
+        let swap_amount = zap_swap_amount(_offer_asset.amount, reserve_in, fee_info.fee_bps)?;
 
-pub mod query {
-    use super::*;
+        let (amount_out, _, _) = swap_hop(
+            &mut _deps,
+            &_env,
+            &_params.pool_address,
+            &Asset {
+                info: _offer_asset.info.clone(),
+                amount: swap_amount,
+            },
+            &token_out,
+        )?;
 
-    pub fn query_pool_data(
-        _deps: Deps,
-        _env: Env,
-        _pool_address: String,
-    ) -> StdResult<PoolDataResponse> {
-        let pool_data = POOL_REGISTER.load(_deps.storage, _pool_address);
+        let response = add_liquidity(
+            _deps,
+            _env,
+            MessageInfo {
+                sender: _depositor,
+                funds: vec![],
+            },
+            AddLiquidityParams {
+                pool_address: _params.pool_address.clone(),
+                token_a: _offer_asset.info,
+                token_b: token_out,
+                amount_a_desired: _offer_asset.amount - swap_amount,
+                amount_b_desired: amount_out,
+                amount_a_min: Uint128::zero(),
+                amount_b_min: Uint128::zero(),
+                address_to: _params.address_to,
+                deadline: _params.deadline,
+                on_receive: _params.on_receive,
+                min_liquidity: Some(_params.min_liquidity),
+            },
+            true,
+        )?;
 
-        match pool_data {
-            Ok(data) => Ok(data),
-            Err(_) => {
-                return Err(StdError::GenericErr {
-                    msg: "Pool does not exist".to_string(),
+        Ok(response
+            .add_attribute("function", "execute_provide_single_sided")
+            .add_attribute("pool_address", _params.pool_address)
+            .add_attribute("swap_amount", swap_amount)
+            .add_attribute("amount_out", amount_out))
+    }
+
+    /**
+     * execute_withdraw_pending: Sends the sender back its pending deposit into `_pool_address`, withdrawals
+     * are never paused.
+     */
+    pub fn execute_withdraw_pending(
+        _deps: DepsMut,
+        _info: MessageInfo,
+        _pool_address: String,
+    ) -> Result<Response, ContractError> {
+        let key = (_info.sender.to_string(), _pool_address.clone());
+        let pending = match release_pending_deposit(_deps.storage, key)? {
+            Some(pending) => pending,
+            None => {
+                return Err(ContractError::NoPendingDeposit {
+                    pool_address: _pool_address,
                 })
             }
+        };
+
+        let mut response = Response::new()
+            .add_attribute("function", "execute_withdraw_pending")
+            .add_attribute("pool_address", _pool_address);
+        for asset in pending {
+            response = response
+                .add_attribute("withdrawn", asset.to_string())
+                .add_message(
+                    asset
+                        .info
+                        .transfer_msg(_info.sender.as_str(), asset.amount)?,
+                );
         }
-    }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, _msg: Reply) -> Result<Response, ContractError> {
-    const EXECUTE_REPLY_ID: u64 = 1u64;
+        Ok(response)
+    }
+
+    /**
+     * execute_set_guardian: Sets or removes the guardian allowed to pause the vault. Only callable by the owner.
+     */
+    pub fn execute_set_guardian(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _guardian: Option<String>,
+    ) -> Result<Response, ContractError> {
+        if VAULT_OWNER.load(_deps.storage)? != _info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        match &_guardian {
+            Some(guardian) => {
+                _deps.api.addr_validate(guardian)?;
+                GUARDIAN.save(_deps.storage, guardian)?;
+            }
+            None => GUARDIAN.remove(_deps.storage),
+        }
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_set_guardian")
+            .add_attribute("guardian", _guardian.unwrap_or_default()))
+    }
+
+    /**
+     * execute_set_fee_to: Sets or removes the address receiving the protocol fee. Only callable by the owner.
+     */
+    pub fn execute_set_fee_to(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _fee_to: Option<String>,
+    ) -> Result<Response, ContractError> {
+        if VAULT_OWNER.load(_deps.storage)? != _info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        match &_fee_to {
+            Some(fee_to) => {
+                _deps.api.addr_validate(fee_to)?;
+                FEE_TO.save(_deps.storage, fee_to)?;
+            }
+            None => FEE_TO.remove(_deps.storage),
+        }
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_set_fee_to")
+            .add_attribute("fee_to", _fee_to.unwrap_or_default()))
+    }
+
+    /**
+     * execute_sync: Sets the reserves of a pool to the balances of the vault left once the reserves of the
+     * other pools are counted, so tokens sent to the vault directly are added to the pool.
+     */
+    pub fn execute_sync(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _pool_address: String,
+    ) -> Result<Response, ContractError> {
+        if VAULT_OWNER.load(_deps.storage)? != _info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let data = match pool_register().load(_deps.storage, _pool_address.clone()) {
+            Ok(data) => data,
+            Err(_) => return Err(ContractError::PoolNotExisted {}),
+        };
+
+        let mut reserves = vec![];
+        for token in [&data.token0, &data.token1] {
+            let balance = token.query_balance(&_deps.querier, _env.contract.address.as_str())?;
+            let other_reserves = token_reserves(_deps.as_ref(), token, Some(&_pool_address))?
+                + pending_deposits(_deps.as_ref(), token)?;
+
+            match balance.checked_sub(other_reserves) {
+                Ok(reserve) => reserves.push(reserve),
+                Err(_) => {
+                    return Err(ContractError::Insolvent {
+                        token: token.to_string(),
+                    })
+                }
+            }
+        }
+
+        execute_update_liquidity(
+            _deps,
+            _env,
+            UpdateLiquidiyParams {
+                pool_address: _pool_address.clone(),
+                amount_a: reserves[0],
+                amount_b: reserves[1],
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_sync")
+            .add_attribute("pool_address", _pool_address)
+            .add_attribute("reserve0", reserves[0])
+            .add_attribute("reserve1", reserves[1]))
+    }
+
+    /**
+     * execute_skim: Sends `_to` the balances of the vault in the tokens of a pool that are above the
+     * reserves of every pool in these tokens.
+     */
+    pub fn execute_skim(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _pool_address: String,
+        _to: String,
+    ) -> Result<Response, ContractError> {
+        if VAULT_OWNER.load(_deps.storage)? != _info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        _deps.api.addr_validate(&_to)?;
+
+        let data = match pool_register().load(_deps.storage, _pool_address.clone()) {
+            Ok(data) => data,
+            Err(_) => return Err(ContractError::PoolNotExisted {}),
+        };
+
+        let mut response = Response::new()
+            .add_attribute("function", "execute_skim")
+            .add_attribute("pool_address", _pool_address);
+
+        for token in [&data.token0, &data.token1] {
+            let balance = token.query_balance(&_deps.querier, _env.contract.address.as_str())?;
+            let excess = balance.saturating_sub(
+                token_reserves(_deps.as_ref(), token, None)?
+                    + pending_deposits(_deps.as_ref(), token)?,
+            );
+
+            if !excess.is_zero() {
+                response = response
+                    .add_attribute(
+                        "skimmed",
+                        Asset {
+                            info: token.clone(),
+                            amount: excess,
+                        }
+                        .to_string(),
+                    )
+                    .add_message(token.transfer_msg(&_to, excess)?);
+            }
+        }
+
+        Ok(response)
+    }
+
+    /**
+     * execute_set_pause: Sets what is halted globally or for a single registered pool. Callable by the owner
+     * and the guardian, withdrawals stay open whatever the pause state is.
+     */
+    pub fn execute_set_pause(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _pool_address: Option<String>,
+        _status: PauseStatus,
+    ) -> Result<Response, ContractError> {
+        let is_owner = VAULT_OWNER.load(_deps.storage)? == _info.sender;
+        let is_guardian = GUARDIAN.may_load(_deps.storage)? == Some(_info.sender.to_string());
+
+        if !is_owner && !is_guardian {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let scope = match _pool_address {
+            Some(pool_address) => {
+                if !pool_register().has(_deps.storage, pool_address.clone()) {
+                    return Err(ContractError::PoolNotExisted {});
+                }
+                POOL_PAUSE.save(_deps.storage, pool_address.clone(), &_status)?;
+                pool_address
+            }
+            None => {
+                GLOBAL_PAUSE.save(_deps.storage, &_status)?;
+                String::from("global")
+            }
+        };
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_set_pause")
+            .add_attribute("pause_scope", scope)
+            .add_attribute("swaps_paused", _status.swaps.to_string())
+            .add_attribute("deposits_paused", _status.deposits.to_string()))
+    }
+
+    /**
+     * withdraw_from_reserves: Pays out `_amount0` and `_amount1` of the reserves of the pool `_pool_address` to
+     * the recipient of `_params`, after checking its minimums. With `single_token_out` the other token is
+     * swapped for it through the pool, at the reserves left after the withdrawal.
+     */
+    pub fn withdraw_from_reserves(
+        mut _deps: DepsMut,
+        _env: Env,
+        _pool_address: String,
+        _pool: PoolDataResponse,
+        _amount0: Uint128,
+        _amount1: Uint128,
+        _params: WithdrawLiquidityParams,
+    ) -> Result<Response, ContractError> {
+        for (amount_out, amount_out_min) in [
+            (_amount0, _params.amount0_min),
+            (_amount1, _params.amount1_min),
+        ] {
+            if amount_out < amount_out_min {
+                return Err(ContractError::SlippageExceeded {
+                    amount_out,
+                    amount_out_min,
+                });
+            }
+        }
+
+        let data = match execute_update_liquidity(
+            _deps.branch(),
+            _env.clone(),
+            UpdateLiquidiyParams {
+                pool_address: _pool_address.clone(),
+                amount_a: _pool.reserve0 - _amount0,
+                amount_b: _pool.reserve1 - _amount1,
+            },
+        ) {
+            Ok(data) => data,
+            Err(_) => {
+                return Err(ContractError::CustomError {
+                    val: String::from("Update Reserve failed"),
+                })
+            }
+        };
+
+        let _execute_messages = match _params.single_token_out {
+            None => vec![
+                _pool.token0.transfer_msg(&_params.address_to, _amount0)?,
+                _pool.token1.transfer_msg(&_params.address_to, _amount1)?,
+            ],
+            Some(single_token_out) => {
+                let (token_out, min_out) = (single_token_out.token_out, single_token_out.min_out);
+                let (other_asset, mut amount_out) = if token_out == _pool.token0 {
+                    (
+                        Asset {
+                            info: _pool.token1,
+                            amount: _amount1,
+                        },
+                        _amount0,
+                    )
+                } else if token_out == _pool.token1 {
+                    (
+                        Asset {
+                            info: _pool.token0,
+                            amount: _amount0,
+                        },
+                        _amount1,
+                    )
+                } else {
+                    return Err(ContractError::AssetMismatch {});
+                };
+
+                // The other token is swapped through the pool, with the reserves left after the withdrawal
+                if !other_asset.amount.is_zero() {
+                    let (swapped, _, _) =
+                        swap_hop(&mut _deps, &_env, &_pool_address, &other_asset, &token_out)?;
+                    amount_out += swapped;
+                }
+
+                if amount_out < min_out {
+                    return Err(ContractError::SlippageExceeded {
+                        amount_out,
+                        amount_out_min: min_out,
+                    });
+                }
+
+                vec![token_out.transfer_msg(&_params.address_to, amount_out)?]
+            }
+        };
+
+        Ok(data.add_messages(_execute_messages))
+    }
+
+    /**
+     * withdraw_liquidity: Asks the pool `_pool_address` to burn the `_liquidity` LP tokens sent to the vault,
+     * the withdrawn amounts are computed and paid out in the reply once the protocol fee is minted.
+     */
+    fn withdraw_liquidity(
+        _deps: DepsMut,
+        _env: Env,
+        _pool_address: String,
+        _liquidity: Uint128,
+        _params: WithdrawLiquidityParams,
+    ) -> Result<Response, ContractError> {
+        const WITHDRAW_LIQUIDITY_REPLY_ID: u64 = 3u64;
+
+        check_deadline(&_env, _params.deadline)?;
+        _deps.api.addr_validate(&_params.address_to)?;
+
+        if pool_register()
+            .load(_deps.storage, _pool_address.clone())
+            .is_err()
+        {
+            return Err(ContractError::PoolNotExisted {});
+        }
+        if _liquidity.is_zero() {
+            return Err(ContractError::InsufficientAmount {});
+        }
+
+        WITHDRAWAL.save(
+            _deps.storage,
+            &WithdrawalState {
+                pool_address: _pool_address.clone(),
+                liquidity: _liquidity,
+                params: _params,
+            },
+        )?;
+
+        let burn = WasmMsg::Execute {
+            contract_addr: _pool_address.clone(),
+            msg: to_binary(&packages::pool_msg::PoolExecuteMsg::BurnFromVault {
+                amount: _liquidity,
+            })?,
+            funds: vec![],
+        };
+
+        Ok(Response::new()
+            .add_attribute("function", "withdraw_liquidity")
+            .add_attribute("pool_address", _pool_address)
+            .add_attribute("liquidity", _liquidity)
+            .add_submessage(SubMsg::reply_on_success(burn, WITHDRAW_LIQUIDITY_REPLY_ID)))
+    }
+
+    pub fn execute_update_liquidity(
+        _deps: DepsMut,
+        _env: Env,
+        _update_liquidity_params: UpdateLiquidiyParams,
+    ) -> Result<Response, ContractError> {
+        let block_time = _env.block.time.seconds();
+        let mut previous_reserves = (Uint128::zero(), Uint128::zero());
+
+        let _update_pool_register = pool_register().update(
+            _deps.storage,
+            _update_liquidity_params.pool_address.clone(),
+            |pool_data| -> Result<PoolDataResponse, ContractError> {
+                match pool_data {
+                    Some(mut pool) => {
+                        // The prices held until now go into the accumulators before the reserves change
+                        accumulate_prices(&mut pool, block_time);
+                        previous_reserves = (pool.reserve0, pool.reserve1);
+                        pool.reserve0 = _update_liquidity_params.amount_a;
+                        pool.reserve1 = _update_liquidity_params.amount_b;
+                        Ok(pool)
+                    }
+                    None => return Err(ContractError::PoolNotExisted {}),
+                }
+            },
+        );
+
+        match _update_pool_register {
+            Ok(pool) => {
+                update_token_reserves(
+                    _deps.storage,
+                    &pool.token0,
+                    previous_reserves.0,
+                    pool.reserve0,
+                )?;
+                update_token_reserves(
+                    _deps.storage,
+                    &pool.token1,
+                    previous_reserves.1,
+                    pool.reserve1,
+                )?;
+                PRICE_OBSERVATIONS.save(
+                    _deps.storage,
+                    (_update_liquidity_params.pool_address.clone(), block_time),
+                    &PriceObservation {
+                        price0_cumulative: pool.price0_cumulative_last,
+                        price1_cumulative: pool.price1_cumulative_last,
+                        reserve0: pool.reserve0,
+                        reserve1: pool.reserve1,
+                    },
+                )?;
+                prune_price_observations(
+                    _deps.storage,
+                    &_update_liquidity_params.pool_address,
+                    block_time,
+                )?;
+
+                Ok(Response::new().add_attribute("function", "execute_update_liquidity"))
+            }
+            Err(_) => return Err(ContractError::UpateLiquidityFailed {}),
+        }
+    }
+
+    /**
+     * execute_receive: Handles the cw20 tokens sent to the vault with `Cw20ExecuteMsg::Send`, the token
+     * sending the message is the token offered to the `VaultHookMsg` it carries.
+     */
+    pub fn execute_receive(
+        mut _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _cw20_receive_msg: Cw20ReceiveMsg,
+    ) -> Result<Response, ContractError> {
+        let offer_asset = Asset {
+            info: AssetInfo::Cw20 {
+                contract_addr: _info.sender.to_string(),
+            },
+            amount: _cw20_receive_msg.amount,
+        };
+
+        match from_binary(&_cw20_receive_msg.msg)? {
+            VaultHookMsg::Swap(params) => execute_swap_tokens(_deps, _env, offer_asset, params),
+            VaultHookMsg::MultiHopSwap(params) => multi_hop_swap(_deps, _env, offer_asset, params),
+            VaultHookMsg::SwapExactOut(params) => {
+                swap_exact_out(_deps, _env, offer_asset, _cw20_receive_msg.sender, params)
+            }
+            VaultHookMsg::DepositPending { pool_address } => {
+                execute_deposit_pending(_deps, _cw20_receive_msg.sender, offer_asset, pool_address)
+            }
+            VaultHookMsg::ProvidePending(params) => {
+                let depositor = _deps.api.addr_validate(&_cw20_receive_msg.sender)?;
+                add_pending_deposit(
+                    _deps.branch(),
+                    depositor.as_str(),
+                    &params.pool_address,
+                    offer_asset,
+                )?;
+                provide_pending(_deps, _env, depositor, params)
+            }
+            VaultHookMsg::ProvideSingleSided(params) => {
+                let depositor = _deps.api.addr_validate(&_cw20_receive_msg.sender)?;
+                provide_single_sided(_deps, _env, depositor, offer_asset, params)
+            }
+            VaultHookMsg::WithdrawLiquidity(params) => withdraw_liquidity(
+                _deps,
+                _env,
+                _info.sender.to_string(),
+                offer_asset.amount,
+                params,
+            ),
+        }
+    }
+
+    /**
+     * execute_swap_tokens: Swaps the cw20 tokens sent to the vault with a `VaultHookMsg::Swap` hook.
+     */
+    pub fn execute_swap_tokens(
+        _deps: DepsMut,
+        _env: Env,
+        _offer_asset: Asset,
+        _swap_token_params: SwapTokensParams,
+    ) -> Result<Response, ContractError> {
+        swap(_deps, _env, _offer_asset, _swap_token_params)
+    }
+
+    /**
+     * native_offer_asset: Returns the native `_token_in` attached to the message, exactly one coin
+     * of its denom must be attached.
+     */
+    fn native_offer_asset(
+        _info: &MessageInfo,
+        _token_in: &AssetInfo,
+    ) -> Result<Asset, ContractError> {
+        if !_token_in.is_native() || _info.funds.len() != 1 {
+            return Err(ContractError::InvalidFunds {
+                val: format!("expected a single {} coin", _token_in),
+            });
+        }
+
+        let offer_asset = Asset {
+            info: _token_in.clone(),
+            amount: _token_in.sent_amount(_info),
+        };
+
+        if offer_asset.amount.is_zero() {
+            return Err(ContractError::InsufficientAmount {});
+        }
+
+        Ok(offer_asset)
+    }
+
+    /**
+     * execute_swap_native_tokens: Swaps the native tokens attached to the message, exactly one coin
+     * of the `token_in` denom must be attached.
+     */
+    pub fn execute_swap_native_tokens(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _swap_token_params: SwapTokensParams,
+    ) -> Result<Response, ContractError> {
+        let offer_asset = native_offer_asset(&_info, &_swap_token_params.token_in)?;
+
+        swap(_deps, _env, offer_asset, _swap_token_params)
+    }
+
+    /**
+     * execute_multi_hop_swap: Swaps the native tokens attached to the message through a path of pools,
+     * exactly one coin of the `token_in` denom must be attached.
+     */
+    pub fn execute_multi_hop_swap(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _multi_hop_swap_params: MultiHopSwapParams,
+    ) -> Result<Response, ContractError> {
+        let offer_asset = native_offer_asset(&_info, &_multi_hop_swap_params.token_in)?;
+
+        multi_hop_swap(_deps, _env, offer_asset, _multi_hop_swap_params)
+    }
+
+    /**
+     * execute_swap_exact_out: Swaps the native tokens attached to the message for an exact output,
+     * exactly one coin of the `token_in` denom must be attached and what isn't swapped is refunded.
+     */
+    pub fn execute_swap_exact_out(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _swap_exact_out_params: SwapExactOutParams,
+    ) -> Result<Response, ContractError> {
+        let offer_asset = native_offer_asset(&_info, &_swap_exact_out_params.token_in)?;
+
+        swap_exact_out(
+            _deps,
+            _env,
+            offer_asset,
+            _info.sender.to_string(),
+            _swap_exact_out_params,
+        )
+    }
+
+    /**
+     * swap_hop: Swaps `_offer_asset` for `_token_out` in the registered pool `_pool_address` and updates the
+     * reserves of the pool in `pool_register`.
+     *
+     * @returns The amount out, and the reserves in and out of the pool before the swap.
+     */
+    fn swap_hop(
+        _deps: &mut DepsMut,
+        _env: &Env,
+        _pool_address: &str,
+        _offer_asset: &Asset,
+        _token_out: &AssetInfo,
+    ) -> Result<(Uint128, Uint128, Uint128), ContractError> {
+        if pause_status(_deps.storage, _pool_address)?.swaps {
+            return Err(ContractError::Paused {
+                action: String::from("Swaps"),
+            });
+        }
+
+        let (data, reserve_in, reserve_out) = pool_reserves(
+            _deps.as_ref(),
+            _pool_address,
+            &_offer_asset.info,
+            _token_out,
+        )?;
+
+        let amount_out: Result<Uint128, _> =
+            _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: _pool_address.to_string(),
+                msg: to_binary(&packages::pool_msg::PoolQueryMsg::GetAmountOut(
+                    packages::pool_msg::AmountOutParams {
+                        amount_in: _offer_asset.amount,
+                        reserve_in,
+                        reserve_out,
+                    },
+                ))?,
+            }));
+
+        let amount_out = match amount_out {
+            Ok(amount_out) => amount_out,
+            Err(_) => {
+                return Err(ContractError::CustomError {
+                    val: "Unable to find amount out".to_string(),
+                })
+            }
+        };
+
+        let updated_reserve_in = reserve_in
+            .checked_add(_offer_asset.amount)
+            .map_err(StdError::from)?;
+        let updated_reserve_out = reserve_out
+            .checked_sub(amount_out)
+            .map_err(StdError::from)?;
+        let (amount_a, amount_b) = if _offer_asset.info == data.token0 {
+            (updated_reserve_in, updated_reserve_out)
+        } else {
+            (updated_reserve_out, updated_reserve_in)
+        };
+
+        match execute_update_liquidity(
+            _deps.branch(),
+            _env.clone(),
+            UpdateLiquidiyParams {
+                pool_address: _pool_address.to_string(),
+                amount_a,
+                amount_b,
+            },
+        ) {
+            Ok(_) => Ok((amount_out, reserve_in, reserve_out)),
+            Err(_) => Err(ContractError::CustomError {
+                val: String::from("Update Reserve failed"),
+            }),
+        }
+    }
+
+    fn swap(
+        mut _deps: DepsMut,
+        _env: Env,
+        _offer_asset: Asset,
+        _swap_token_params: SwapTokensParams,
+    ) -> Result<Response, ContractError> {
+        if _offer_asset.info != _swap_token_params.token_in {
+            return Err(ContractError::AssetMismatch {});
+        }
+
+        check_deadline(&_env, _swap_token_params.deadline)?;
+
+        let (_amount_out, reserve_in, reserve_out) = swap_hop(
+            &mut _deps,
+            &_env,
+            &_swap_token_params.pool_address,
+            &_offer_asset,
+            &_swap_token_params.token_out,
+        )?;
+
+        if _amount_out < _swap_token_params.amount_out_min {
+            return Err(ContractError::SlippageExceeded {
+                amount_out: _amount_out,
+                amount_out_min: _swap_token_params.amount_out_min,
+            });
+        }
+
+        if let Some(max_price_impact) = _swap_token_params.max_price_impact {
+            let price_impact =
+                price_impact(_offer_asset.amount, _amount_out, reserve_in, reserve_out);
+            if price_impact > max_price_impact {
+                return Err(ContractError::PriceImpactExceeded {
+                    price_impact,
+                    max_price_impact,
+                });
+            }
+        }
+
+        let execute_message = _swap_token_params
+            .token_out
+            .transfer_msg(&_swap_token_params.address_to, _amount_out)?;
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_swap")
+            .add_attribute("pool_address", _swap_token_params.pool_address)
+            .add_attribute("amount_in", _offer_asset.to_string())
+            .add_attribute("amount_out", _amount_out.to_string())
+            .add_message(execute_message))
+    }
+
+    /**
+     * execute_flash_loan: Sends the assets out of the reserves of their pools to `_recipient` and calls it back.
+     * The reply to the callback checks the balances of the vault and credits the fees to the pools.
+     */
+    pub fn execute_flash_loan(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _assets: Vec<FlashLoanAsset>,
+        _recipient: String,
+        _msg: Binary,
+    ) -> Result<Response, ContractError> {
+        const FLASH_LOAN_REPLY_ID: u64 = 2u64;
+
+        if _assets.is_empty() {
+            return Err(ContractError::InsufficientAmount {});
+        }
+
+        // Attached funds would be counted in the balances the loan is checked against
+        if !_info.funds.is_empty() {
+            return Err(ContractError::FlashLoanNonPayable {});
+        }
+
+        let recipient = _deps.api.addr_validate(&_recipient)?;
+
+        let mut flash_loan = FlashLoanState {
+            expected_balances: vec![],
+            pool_fees: vec![],
+        };
+        let mut execute_messages = vec![];
+
+        for (index, loan) in _assets.iter().enumerate() {
+            // Every entry is checked against the whole reserve, so a token is lent once per pool
+            if _assets[..index].iter().any(|other| {
+                other.pool_address == loan.pool_address && other.asset.info == loan.asset.info
+            }) {
+                return Err(ContractError::DuplicateFlashLoanAsset {
+                    pool_address: loan.pool_address.clone(),
+                    token: loan.asset.info.to_string(),
+                });
+            }
+
+            if pause_status(_deps.storage, &loan.pool_address)?.swaps {
+                return Err(ContractError::Paused {
+                    action: String::from("Flash loans"),
+                });
+            }
+
+            let data = match pool_register().load(_deps.storage, loan.pool_address.clone()) {
+                Ok(data) => data,
+                Err(_) => return Err(ContractError::PoolNotExisted {}),
+            };
+
+            let reserve = if loan.asset.info == data.token0 {
+                data.reserve0
+            } else if loan.asset.info == data.token1 {
+                data.reserve1
+            } else {
+                return Err(ContractError::AssetMismatch {});
+            };
+
+            if loan.asset.amount.is_zero() {
+                return Err(ContractError::InsufficientAmount {});
+            }
+            if loan.asset.amount > reserve {
+                return Err(ContractError::InsufficientLiquidity {});
+            }
+
+            let fee_info: packages::pool_msg::FeeInfoResponse =
+                _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: loan.pool_address.clone(),
+                    msg: to_binary(&packages::pool_msg::PoolQueryMsg::FeeInfo {})?,
+                }))?;
+            // Rounded up, so no loan is small enough to be free
+            let fee = Asset {
+                info: loan.asset.info.clone(),
+                amount: loan.asset.amount.mul_ceil(Decimal::from_ratio(
+                    fee_info.fee_bps,
+                    packages::pool_msg::FEE_DENOMINATOR,
+                )),
+            };
+
+            // The lent amount comes back, the balance of the vault must grow by the fees only
+            match flash_loan
+                .expected_balances
+                .iter_mut()
+                .find(|balance| balance.info == fee.info)
+            {
+                Some(balance) => balance.amount += fee.amount,
+                None => {
+                    let balance = fee
+                        .info
+                        .query_balance(&_deps.querier, _env.contract.address.as_str())?;
+                    flash_loan.expected_balances.push(Asset {
+                        info: fee.info.clone(),
+                        amount: balance + fee.amount,
+                    });
+                }
+            }
+
+            flash_loan.pool_fees.push((loan.pool_address.clone(), fee));
+            execute_messages.push(
+                loan.asset
+                    .info
+                    .transfer_msg(recipient.as_str(), loan.asset.amount)?,
+            );
+        }
+
+        FLASH_LOAN.save(_deps.storage, &flash_loan)?;
+
+        let callback = WasmMsg::Execute {
+            contract_addr: recipient.to_string(),
+            msg: to_binary(&FlashLoanReceiverMsg::FlashLoanCallback {
+                fees: flash_loan
+                    .pool_fees
+                    .into_iter()
+                    .map(|(_, fee)| fee)
+                    .collect(),
+                assets: _assets,
+                msg: _msg,
+            })?,
+            funds: vec![],
+        };
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_flash_loan")
+            .add_attribute("recipient", recipient)
+            .add_messages(execute_messages)
+            .add_submessage(SubMsg::reply_on_success(callback, FLASH_LOAN_REPLY_ID)))
+    }
+
+    /**
+     * swap_exact_out: Swaps the part of `_offer_asset` needed for exactly `amount_out` of `token_out`, using
+     * the `GetAmountIn` of the pool. The input must not exceed `amount_in_max` nor the amount sent, the
+     * remainder of the amount sent is refunded to `_refund_to`.
+     */
+    fn swap_exact_out(
+        mut _deps: DepsMut,
+        _env: Env,
+        _offer_asset: Asset,
+        _refund_to: String,
+        _swap_exact_out_params: SwapExactOutParams,
+    ) -> Result<Response, ContractError> {
+        if _offer_asset.info != _swap_exact_out_params.token_in {
+            return Err(ContractError::AssetMismatch {});
+        }
+
+        check_deadline(&_env, _swap_exact_out_params.deadline)?;
+
+        let (_, reserve_in, reserve_out) = pool_reserves(
+            _deps.as_ref(),
+            &_swap_exact_out_params.pool_address,
+            &_swap_exact_out_params.token_in,
+            &_swap_exact_out_params.token_out,
+        )?;
+
+        let amount_in: Result<Uint128, _> =
+            _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: _swap_exact_out_params.pool_address.clone(),
+                msg: to_binary(&packages::pool_msg::PoolQueryMsg::GetAmountIn(
+                    packages::pool_msg::AmountInParams {
+                        amount_out: _swap_exact_out_params.amount_out,
+                        reserve_in,
+                        reserve_out,
+                    },
+                ))?,
+            }));
+
+        let amount_in = match amount_in {
+            Ok(amount_in) => amount_in,
+            Err(_) => {
+                return Err(ContractError::CustomError {
+                    val: "Unable to find amount in".to_string(),
+                })
+            }
+        };
+
+        let amount_in_max = _swap_exact_out_params
+            .amount_in_max
+            .min(_offer_asset.amount);
+        if amount_in > amount_in_max {
+            return Err(ContractError::ExcessiveInputAmount {
+                amount_in,
+                amount_in_max,
+            });
+        }
+
+        // The rounding of `GetAmountIn` can make the output a bit larger than asked, never smaller
+        let (amount_out, _, _) = swap_hop(
+            &mut _deps,
+            &_env,
+            &_swap_exact_out_params.pool_address,
+            &Asset {
+                info: _offer_asset.info.clone(),
+                amount: amount_in,
+            },
+            &_swap_exact_out_params.token_out,
+        )?;
+
+        let mut execute_messages = vec![_swap_exact_out_params
+            .token_out
+            .transfer_msg(&_swap_exact_out_params.address_to, amount_out)?];
+
+        let refund = _offer_asset.amount - amount_in;
+        if !refund.is_zero() {
+            execute_messages.push(_offer_asset.info.transfer_msg(&_refund_to, refund)?);
+        }
+
+        Ok(Response::new()
+            .add_attribute("function", "execute_swap_exact_out")
+            .add_attribute("pool_address", _swap_exact_out_params.pool_address)
+            .add_attribute("amount_in", amount_in.to_string())
+            .add_attribute("amount_out", amount_out.to_string())
+            .add_attribute("refund", refund.to_string())
+            .add_messages(execute_messages))
+    }
+
+    /**
+     * multi_hop_swap: Swaps `_offer_asset` through every pool of the path in order, the output of a hop is the
+     * input of the next one. The reserves of every pool are updated in the same transaction and only the
+     * final token is sent, once the last output is checked against `amount_out_min`.
+     */
+    fn multi_hop_swap(
+        mut _deps: DepsMut,
+        _env: Env,
+        _offer_asset: Asset,
+        _multi_hop_swap_params: MultiHopSwapParams,
+    ) -> Result<Response, ContractError> {
+        if _offer_asset.info != _multi_hop_swap_params.token_in {
+            return Err(ContractError::AssetMismatch {});
+        }
+
+        check_deadline(&_env, _multi_hop_swap_params.deadline)?;
+
+        let hops = _multi_hop_swap_params.operations.len();
+        if hops == 0 || hops > MAX_SWAP_HOPS {
+            return Err(ContractError::InvalidPath {
+                val: format!("expected 1 to {} hops, got {}", MAX_SWAP_HOPS, hops),
+            });
+        }
+
+        let mut response = Response::new()
+            .add_attribute("function", "execute_multi_hop_swap")
+            .add_attribute("amount_in", _offer_asset.to_string());
+
+        let mut offer_asset = _offer_asset;
+        for operation in _multi_hop_swap_params.operations {
+            let (amount_out, _, _) = swap_hop(
+                &mut _deps,
+                &_env,
+                &operation.pool_address,
+                &offer_asset,
+                &operation.token_out,
+            )?;
+
+            response = response.add_attribute("pool_address", operation.pool_address);
+            offer_asset = Asset {
+                info: operation.token_out,
+                amount: amount_out,
+            };
+        }
+
+        if offer_asset.amount < _multi_hop_swap_params.amount_out_min {
+            return Err(ContractError::SlippageExceeded {
+                amount_out: offer_asset.amount,
+                amount_out_min: _multi_hop_swap_params.amount_out_min,
+            });
+        }
+
+        let execute_message = offer_asset
+            .info
+            .transfer_msg(&_multi_hop_swap_params.address_to, offer_asset.amount)?;
+
+        Ok(response
+            .add_attribute("amount_out", offer_asset.to_string())
+            .add_message(execute_message))
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(_deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::QueryPoolData { pool_address } => {
+            to_binary(&query::query_pool_data(_deps, _env, pool_address)?)
+        }
+        QueryMsg::AllPools { start_after, limit } => {
+            to_binary(&query::query_all_pools(_deps, start_after, limit)?)
+        }
+        QueryMsg::PoolsByToken {
+            token,
+            start_after,
+            limit,
+        } => to_binary(&query::query_pools_by_token(
+            _deps,
+            token,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Config { start_after, limit } => {
+            to_binary(&query::query_config(_deps, start_after, limit)?)
+        }
+        QueryMsg::PendingDeposits {
+            depositor,
+            pool_address,
+        } => to_binary(&query::query_pending_deposits(
+            _deps,
+            depositor,
+            pool_address,
+        )?),
+        QueryMsg::FactoryRegistered { factory_address } => to_binary(&FactoryRegisteredResponse {
+            registered: FACTORY_REGISTER
+                .may_load(_deps.storage, factory_address)?
+                .unwrap_or_default(),
+        }),
+        QueryMsg::PauseInfo { pool_address } => {
+            to_binary(&query::query_pause_info(_deps, pool_address)?)
+        }
+        QueryMsg::Solvency {} => to_binary(&query::query_solvency(_deps, _env)?),
+        QueryMsg::FeeTo {} => to_binary(&FeeToResponse {
+            fee_to: FEE_TO.may_load(_deps.storage)?,
+        }),
+        QueryMsg::SimulateSwap {
+            pool_address,
+            offer_asset,
+        } => to_binary(&query::query_simulate_swap(
+            _deps,
+            pool_address,
+            offer_asset,
+        )?),
+        QueryMsg::ReverseSimulateSwap {
+            pool_address,
+            ask_asset,
+        } => to_binary(&query::query_reverse_simulate_swap(
+            _deps,
+            pool_address,
+            ask_asset,
+        )?),
+        QueryMsg::Twap {
+            pool_address,
+            start_time,
+            end_time,
+        } => to_binary(&query::query_twap(
+            _deps,
+            _env,
+            pool_address,
+            start_time,
+            end_time,
+        )?),
+    }
+}
+
+pub mod query {
+    use super::*;
+    use packages::pool_msg::{AmountInParams, AmountOutParams, FeeInfoResponse, PoolQueryMsg};
+    use packages::vault_msg::{
+        ConfigResponse, PauseInfoResponse, PendingDeposit, PendingDepositsResponse, PoolResponse,
+        PoolsResponse, SolvencyResponse, SwapSimulationResponse, TokenSolvency, TwapResponse,
+    };
+    use std::collections::BTreeMap;
+
+    pub fn query_all_pools(
+        _deps: Deps,
+        _start_after: Option<String>,
+        _limit: Option<u32>,
+    ) -> StdResult<PoolsResponse> {
+        let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = _start_after.map(Bound::exclusive);
+
+        let pools = pool_register()
+            .range(_deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(pool_address, pool_data)| PoolResponse {
+                    pool_address,
+                    pool_data,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(PoolsResponse { pools })
+    }
+
+    /**
+     * query_pools_by_token: Lists the pools holding `_token`, a page is read from each of the two token
+     * indexes and they are merged in the order of the pool contract addresses.
+     */
+    pub fn query_pools_by_token(
+        _deps: Deps,
+        _token: AssetInfo,
+        _start_after: Option<String>,
+        _limit: Option<u32>,
+    ) -> StdResult<PoolsResponse> {
+        let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let register = pool_register();
+
+        let mut pools = vec![];
+        for index in [&register.idx.token0, &register.idx.token1] {
+            let start = _start_after.clone().map(Bound::exclusive);
+            for item in index
+                .prefix(_token.as_str().to_string())
+                .range(_deps.storage, start, None, Order::Ascending)
+                .take(limit)
+            {
+                let (pool_address, pool_data) = item?;
+                pools.push(PoolResponse {
+                    pool_address,
+                    pool_data,
+                });
+            }
+        }
+
+        pools.sort_by(|a, b| a.pool_address.cmp(&b.pool_address));
+        pools.truncate(limit);
+
+        Ok(PoolsResponse { pools })
+    }
+
+    pub fn query_pending_deposits(
+        _deps: Deps,
+        _depositor: String,
+        _pool_address: Option<String>,
+    ) -> StdResult<PendingDepositsResponse> {
+        let deposits = match _pool_address {
+            Some(pool_address) => PENDING_DEPOSITS
+                .may_load(_deps.storage, (_depositor, pool_address.clone()))?
+                .map(|assets| PendingDeposit {
+                    pool_address,
+                    assets,
+                })
+                .into_iter()
+                .collect(),
+            None => PENDING_DEPOSITS
+                .prefix(_depositor)
+                .range(_deps.storage, None, None, Order::Ascending)
+                .map(|item| {
+                    item.map(|(pool_address, assets)| PendingDeposit {
+                        pool_address,
+                        assets,
+                    })
+                })
+                .collect::<StdResult<Vec<_>>>()?,
+        };
+
+        Ok(PendingDepositsResponse { deposits })
+    }
+
+    pub fn query_config(
+        _deps: Deps,
+        _start_after: Option<String>,
+        _limit: Option<u32>,
+    ) -> StdResult<ConfigResponse> {
+        let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = _start_after.map(Bound::exclusive);
+
+        let factories = FACTORY_REGISTER
+            .range(_deps.storage, start, None, Order::Ascending)
+            .filter(|item| !matches!(item, Ok((_, false))))
+            .take(limit)
+            .map(|item| item.map(|(factory_address, _)| factory_address))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ConfigResponse {
+            owner: VAULT_OWNER.load(_deps.storage)?,
+            factories,
+        })
+    }
+
+    pub fn query_solvency(_deps: Deps, _env: Env) -> StdResult<SolvencyResponse> {
+        let mut reserves: BTreeMap<AssetInfo, Uint128> = BTreeMap::new();
+
+        for item in pool_register().range(_deps.storage, None, None, Order::Ascending) {
+            let (_, data) = item?;
+            *reserves.entry(data.token0).or_default() += data.reserve0;
+            *reserves.entry(data.token1).or_default() += data.reserve1;
+        }
+
+        let mut tokens = vec![];
+        for (token, reserves) in reserves {
+            let balance = token.query_balance(&_deps.querier, _env.contract.address.as_str())?;
+            let pending = pending_deposits(_deps, &token)?;
+            tokens.push(TokenSolvency {
+                token,
+                reserves,
+                balance,
+                pending,
+            });
+        }
+
+        Ok(SolvencyResponse {
+            solvent: tokens
+                .iter()
+                .all(|token| token.balance >= token.reserves + token.pending),
+            tokens,
+        })
+    }
+
+    /**
+     * swap_reserves: Returns the reserves in and out of the pool for a swap from `_token`, when
+     * `_token_is_input`, or to `_token` otherwise. The other token of the swap is the other token of the pool.
+     */
+    fn swap_reserves(
+        _deps: Deps,
+        _pool_address: &str,
+        _token: &AssetInfo,
+        _token_is_input: bool,
+    ) -> StdResult<(Uint128, Uint128)> {
+        let data = match pool_register().load(_deps.storage, _pool_address.to_string()) {
+            Ok(data) => data,
+            Err(_) => return Err(StdError::generic_err("Pool does not exist")),
+        };
+
+        let other_token = if _token == &data.token0 {
+            data.token1
+        } else {
+            data.token0
+        };
+        let (token_in, token_out) = if _token_is_input {
+            (_token, &other_token)
+        } else {
+            (&other_token, _token)
+        };
+
+        match pool_reserves(_deps, _pool_address, token_in, token_out) {
+            Ok((_, reserve_in, reserve_out)) => Ok((reserve_in, reserve_out)),
+            Err(err) => Err(StdError::generic_err(err.to_string())),
+        }
+    }
+
+    /**
+     * swap_simulation: Builds the simulation of a swap of `_amount_in` for `_amount_out` in a pool with the
+     * reserves `_reserve_in` and `_reserve_out`, the fee is the one of the pool.
+     */
+    fn swap_simulation(
+        _deps: Deps,
+        _pool_address: &str,
+        _amount_in: Uint128,
+        _amount_out: Uint128,
+        _reserve_in: Uint128,
+        _reserve_out: Uint128,
+    ) -> StdResult<SwapSimulationResponse> {
+        let fee_info: FeeInfoResponse =
+            _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: _pool_address.to_string(),
+                msg: to_binary(&PoolQueryMsg::FeeInfo {})?,
+            }))?;
+
+        let spot_price = |reserve_in: Uint128, reserve_out: Uint128| {
+            Decimal::checked_from_ratio(reserve_out, reserve_in)
+                .map_err(|err| StdError::generic_err(err.to_string()))
+        };
+
+        Ok(SwapSimulationResponse {
+            amount_in: _amount_in,
+            amount_out: _amount_out,
+            fee_amount: _amount_in
+                .multiply_ratio(fee_info.fee_bps, packages::pool_msg::FEE_DENOMINATOR),
+            spot_price_before: spot_price(_reserve_in, _reserve_out)?,
+            spot_price_after: spot_price(
+                _reserve_in.checked_add(_amount_in)?,
+                _reserve_out.checked_sub(_amount_out)?,
+            )?,
+            price_impact: price_impact(_amount_in, _amount_out, _reserve_in, _reserve_out),
+        })
+    }
+
+    pub fn query_simulate_swap(
+        _deps: Deps,
+        _pool_address: String,
+        _offer_asset: Asset,
+    ) -> StdResult<SwapSimulationResponse> {
+        let (reserve_in, reserve_out) =
+            swap_reserves(_deps, &_pool_address, &_offer_asset.info, true)?;
+
+        let amount_out: Uint128 = _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: _pool_address.clone(),
+            msg: to_binary(&PoolQueryMsg::GetAmountOut(AmountOutParams {
+                amount_in: _offer_asset.amount,
+                reserve_in,
+                reserve_out,
+            }))?,
+        }))?;
+
+        swap_simulation(
+            _deps,
+            &_pool_address,
+            _offer_asset.amount,
+            amount_out,
+            reserve_in,
+            reserve_out,
+        )
+    }
+
+    pub fn query_reverse_simulate_swap(
+        _deps: Deps,
+        _pool_address: String,
+        _ask_asset: Asset,
+    ) -> StdResult<SwapSimulationResponse> {
+        let (reserve_in, reserve_out) =
+            swap_reserves(_deps, &_pool_address, &_ask_asset.info, false)?;
+
+        let amount_in: Uint128 = _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: _pool_address.clone(),
+            msg: to_binary(&PoolQueryMsg::GetAmountIn(AmountInParams {
+                amount_out: _ask_asset.amount,
+                reserve_in,
+                reserve_out,
+            }))?,
+        }))?;
+
+        swap_simulation(
+            _deps,
+            &_pool_address,
+            amount_in,
+            _ask_asset.amount,
+            reserve_in,
+            reserve_out,
+        )
+    }
+
+    pub fn query_pause_info(
+        _deps: Deps,
+        _pool_address: Option<String>,
+    ) -> StdResult<PauseInfoResponse> {
+        let global = GLOBAL_PAUSE.may_load(_deps.storage)?.unwrap_or_default();
+
+        let (pool, effective) = match _pool_address {
+            Some(pool_address) => (
+                Some(
+                    POOL_PAUSE
+                        .may_load(_deps.storage, pool_address.clone())?
+                        .unwrap_or_default(),
+                ),
+                pause_status(_deps.storage, &pool_address)?,
+            ),
+            None => (None, global),
+        };
+
+        Ok(PauseInfoResponse {
+            guardian: GUARDIAN.may_load(_deps.storage)?,
+            global,
+            pool,
+            effective,
+        })
+    }
+
+    /**
+     * query_twap: Returns the time weighted average prices of the pool between `_start_time` and `_end_time`,
+     * the difference of its price accumulators between them divided by the seconds in between.
+     */
+    pub fn query_twap(
+        _deps: Deps,
+        _env: Env,
+        _pool_address: String,
+        _start_time: u64,
+        _end_time: u64,
+    ) -> StdResult<TwapResponse> {
+        let block_time = _env.block.time.seconds();
+        if _start_time >= _end_time
+            || _end_time > block_time
+            || _start_time < block_time.saturating_sub(MAX_TWAP_WINDOW)
+        {
+            return Err(StdError::GenericErr {
+                msg: "Invalid time window".to_string(),
+            });
+        }
+
+        let data = query_pool_data(_deps, _env, _pool_address.clone())?;
+        let (price0_start, price1_start) =
+            price_cumulatives_at(_deps, &_pool_address, _start_time)?;
+        let (price0_end, price1_end) = price_cumulatives_at(_deps, &_pool_address, _end_time)?;
+        let seconds = Decimal256::from_ratio(_end_time - _start_time, 1u64);
+
+        Ok(TwapResponse {
+            token0: data.token0,
+            token1: data.token1,
+            price0_average: (price0_end - price0_start) / seconds,
+            price1_average: (price1_end - price1_start) / seconds,
+        })
+    }
+
+    pub fn query_pool_data(
+        _deps: Deps,
+        _env: Env,
+        _pool_address: String,
+    ) -> StdResult<PoolDataResponse> {
+        let pool_data = pool_register().load(_deps.storage, _pool_address);
+
+        match pool_data {
+            Ok(data) => Ok(data),
+            Err(_) => {
+                return Err(StdError::GenericErr {
+                    msg: "Pool does not exist".to_string(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, _msg: Reply) -> Result<Response, ContractError> {
+    const EXECUTE_REPLY_ID: u64 = 1u64;
+    const FLASH_LOAN_REPLY_ID: u64 = 2u64;
+    const WITHDRAW_LIQUIDITY_REPLY_ID: u64 = 3u64;
 
     match _msg.id {
         EXECUTE_REPLY_ID => reply::update_pool_reserve(_deps, _env, _msg),
+        FLASH_LOAN_REPLY_ID => reply::settle_flash_loan(_deps, _env),
+        WITHDRAW_LIQUIDITY_REPLY_ID => reply::settle_withdrawal(_deps, _env),
         _id => {
             return Err(ContractError::CustomError {
                 val: String::from("Id doesn't match"),
@@ -605,6 +2514,94 @@ pub mod reply {
     use cosmwasm_std::from_binary;
     use cw0::parse_reply_execute_data;
 
+    /**
+     * settle_flash_loan: Checks that the recipient of the flash loan sent the assets back with the fees, then
+     * credits the fees to the reserves of the pools they were lent from.
+     */
+    pub fn settle_flash_loan(mut _deps: DepsMut, _env: Env) -> Result<Response, ContractError> {
+        let flash_loan = FLASH_LOAN.load(_deps.storage)?;
+
+        for expected in &flash_loan.expected_balances {
+            let balance = expected
+                .info
+                .query_balance(&_deps.querier, _env.contract.address.as_str())?;
+
+            if balance < expected.amount {
+                return Err(ContractError::FlashLoanNotRepaid {
+                    val: format!("expected a balance of {}, got {}", expected, balance),
+                });
+            }
+        }
+
+        for (pool_address, fee) in flash_loan.pool_fees {
+            let mut pool = match pool_register().load(_deps.storage, pool_address.clone()) {
+                Ok(pool) => pool,
+                Err(_) => return Err(ContractError::PoolNotExisted {}),
+            };
+            if fee.info == pool.token0 {
+                pool.reserve0 += fee.amount;
+            } else {
+                pool.reserve1 += fee.amount;
+            }
+
+            execute::execute_update_liquidity(
+                _deps.branch(),
+                _env.clone(),
+                UpdateLiquidiyParams {
+                    pool_address,
+                    amount_a: pool.reserve0,
+                    amount_b: pool.reserve1,
+                },
+            )?;
+        }
+
+        FLASH_LOAN.remove(_deps.storage);
+
+        Ok(Response::new().add_attribute("function", "settle_flash_loan"))
+    }
+
+    /**
+     * settle_withdrawal: Pays out the LP tokens burned by the pool, their share of the reserves of the vault
+     * in the LP supply before the burn, which includes the protocol fee minted by the pool.
+     */
+    pub fn settle_withdrawal(_deps: DepsMut, _env: Env) -> Result<Response, ContractError> {
+        let withdrawal = WITHDRAWAL.load(_deps.storage)?;
+        WITHDRAWAL.remove(_deps.storage);
+
+        let pool = match pool_register().load(_deps.storage, withdrawal.pool_address.clone()) {
+            Ok(pool) => pool,
+            Err(_) => return Err(ContractError::PoolNotExisted {}),
+        };
+        let token_info: cw20::TokenInfoResponse =
+            _deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: withdrawal.pool_address.clone(),
+                msg: to_binary(&packages::pool_msg::PoolQueryMsg::TokenInfo {})?,
+            }))?;
+
+        let total_supply = token_info.total_supply + withdrawal.liquidity;
+        let amount0 = withdrawal
+            .liquidity
+            .multiply_ratio(pool.reserve0, total_supply);
+        let amount1 = withdrawal
+            .liquidity
+            .multiply_ratio(pool.reserve1, total_supply);
+
+        let response = execute::withdraw_from_reserves(
+            _deps,
+            _env,
+            withdrawal.pool_address,
+            pool,
+            amount0,
+            amount1,
+            withdrawal.params,
+        )?;
+
+        Ok(response
+            .add_attribute("function", "settle_withdrawal")
+            .add_attribute("amount0", amount0)
+            .add_attribute("amount1", amount1))
+    }
+
     pub fn update_pool_reserve(
         _deps: DepsMut,
         _env: Env,
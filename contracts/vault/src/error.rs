@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError, Uint128};
 use serde::{Serialize, Serializer};
 use thiserror::Error;
 
@@ -46,6 +46,57 @@ pub enum ContractError {
     #[error("Unable to swap")]
     SwapFailed {},
 
+    #[error("Assets don't match the pool")]
+    AssetMismatch {},
+
+    #[error("Invalid funds: {val}")]
+    InvalidFunds { val: String },
+
+    #[error("{action} are paused")]
+    Paused { action: String },
+
+    #[error("Expired: deadline {deadline} is before the block time {block_time}")]
+    Expired { deadline: Uint128, block_time: u64 },
+
+    #[error("Slippage exceeded: amount out {amount_out} is below the minimum {amount_out_min}")]
+    SlippageExceeded {
+        amount_out: Uint128,
+        amount_out_min: Uint128,
+    },
+
+    #[error("Excessive input amount: {amount_in} is above the maximum {amount_in_max}")]
+    ExcessiveInputAmount {
+        amount_in: Uint128,
+        amount_in_max: Uint128,
+    },
+
+    #[error("Insolvent: the vault holds less {token} than the reserves of its pools")]
+    Insolvent { token: String },
+
+    #[error("A flash loan is in progress")]
+    FlashLoanInProgress {},
+
+    #[error("Flash loan not repaid: {val}")]
+    FlashLoanNotRepaid { val: String },
+
+    #[error("Flash loan lends {token} of pool {pool_address} more than once")]
+    DuplicateFlashLoanAsset { pool_address: String, token: String },
+
+    #[error("Flash loans don't accept funds, the borrowed assets are repaid by the recipient")]
+    FlashLoanNonPayable {},
+
+    #[error("Invalid swap path: {val}")]
+    InvalidPath { val: String },
+
+    #[error("Price impact {price_impact} exceeds the maximum {max_price_impact}")]
+    PriceImpactExceeded {
+        price_impact: Decimal,
+        max_price_impact: Decimal,
+    },
+
+    #[error("No pending deposit into {pool_address}")]
+    NoPendingDeposit { pool_address: String },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
     // Add any other custom errors you like here.
@@ -1,9 +1,25 @@
-use cw_storage_plus::{Item, Map};
-use packages::vault_msg::PoolDataResponse;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal256, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use packages::asset::Asset;
+use packages::vault_msg::{PauseStatus, PoolDataResponse, WithdrawLiquidityParams};
 
 // VAULT_OWNER is used to store the address of the vault owner in the state.
 pub const VAULT_OWNER: Item<String> = Item::new("vault_owner");
 
+// FEE_TO is the address receiving the protocol fee of the pools, the fee is off when it isn't set.
+pub const FEE_TO: Item<String> = Item::new("fee_to");
+
+// GUARDIAN is an optional address allowed to pause the vault next to its owner.
+pub const GUARDIAN: Item<String> = Item::new("guardian");
+
+/**
+ * GLOBAL_PAUSE and POOL_PAUSE: What is halted for every pool and for a single pool, keyed by the
+ * pool contract address. A pool is halted when either of them halts it, withdrawals are never halted.
+ */
+pub const GLOBAL_PAUSE: Item<PauseStatus> = Item::new("global_pause");
+pub const POOL_PAUSE: Map<String, PauseStatus> = Map::new("pool_pause");
+
 /**
  * FACTORY_REGISTER: This constant represents a mapping used to track the registration
  * status of factory contracts in the system. Each key in the mapping corresponds to a
@@ -21,13 +37,93 @@ pub const VAULT_OWNER: Item<String> = Item::new("vault_owner");
 
 pub const FACTORY_REGISTER: Map<String, bool> = Map::new("factory_register");
 
+/// PoolIndexes: Indexes of the registered pools by each of their two tokens, keyed by `AssetInfo::as_str`
+pub struct PoolIndexes<'a> {
+    pub token0: MultiIndex<'a, String, PoolDataResponse, String>,
+    pub token1: MultiIndex<'a, String, PoolDataResponse, String>,
+}
+
+impl<'a> IndexList<PoolDataResponse> for PoolIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<PoolDataResponse>> + '_> {
+        let indexes: Vec<&dyn Index<PoolDataResponse>> = vec![&self.token0, &self.token1];
+        Box::new(indexes.into_iter())
+    }
+}
+
 /**
- * `POOL_REGISTER` is a mapping used to store information about registered pool contracts in the vault.
+ * `pool_register` is a mapping used to store information about registered pool contracts in the vault.
  *
  * When a pool contract is registered in the vault contract, all the relevant data is saved in this mapping.
  * The key is the pool contract's address, and the associated value is an instance of the `PoolData` struct,
  * containing details about the pool's token pair, reserves, and LP token contract.
- * 
- * note: we are sending pool addresses in the events so that you can find addressess and store them for fetching pool data
+ *
+ * note: the pools are indexed by token, the `AllPools` and `PoolsByToken` queries list them
+ */
+pub fn pool_register<'a>() -> IndexedMap<'a, String, PoolDataResponse, PoolIndexes<'a>> {
+    let indexes = PoolIndexes {
+        token0: MultiIndex::new(
+            |pool: &PoolDataResponse| pool.token0.as_str().to_string(),
+            "pool_register",
+            "pool_register__token0",
+        ),
+        token1: MultiIndex::new(
+            |pool: &PoolDataResponse| pool.token1.as_str().to_string(),
+            "pool_register",
+            "pool_register__token1",
+        ),
+    };
+
+    IndexedMap::new("pool_register", indexes)
+}
+
+#[cw_serde]
+pub struct PriceObservation {
+    pub price0_cumulative: Decimal256,
+    pub price1_cumulative: Decimal256,
+    /// Reserves of the pool after the change, the prices holding until the next observation
+    pub reserve0: Uint128,
+    pub reserve1: Uint128,
+}
+
+/**
+ * PRICE_OBSERVATIONS: The price accumulators of a pool at every block its reserves changed in, keyed by
+ * the pool contract address and the block time in unix seconds. The time weighted average price between
+ * two timestamps is read from them. Observations older than `MAX_TWAP_WINDOW` are pruned, except the last
+ * one of them the accumulators at the start of the window are read from.
+ */
+pub const PRICE_OBSERVATIONS: Map<(String, u64), PriceObservation> = Map::new("price_observations");
+
+/**
+ * PENDING_DEPOSITS: Tokens sent to the vault waiting to be provided as liquidity, keyed by the depositor and
+ * the pool contract address. They aren't part of the reserves of the pool until they are provided.
  */
-pub const POOL_REGISTER: Map<String, PoolDataResponse> = Map::new("pool_register");
\ No newline at end of file
+pub const PENDING_DEPOSITS: Map<(String, String), Vec<Asset>> = Map::new("pending_deposits");
+
+/// TOKEN_RESERVES: The sum of the reserves of every registered pool in a token, keyed by the token
+pub const TOKEN_RESERVES: Map<&str, Uint128> = Map::new("token_reserves");
+
+/// PENDING_DEPOSIT_TOTALS: The sum of the pending deposits of every depositor in a token, keyed by the token
+pub const PENDING_DEPOSIT_TOTALS: Map<&str, Uint128> = Map::new("pending_deposit_totals");
+
+#[cw_serde]
+pub struct FlashLoanState {
+    /// Balance of the vault every lent asset must be back to, its balance before the loan plus the fees
+    pub expected_balances: Vec<Asset>,
+    /// Fee owed to each pool, credited to its reserves once the loan is repaid
+    pub pool_fees: Vec<(String, Asset)>,
+}
+
+// FLASH_LOAN holds the open flash loan between the transfer of the assets and the reply settling it.
+pub const FLASH_LOAN: Item<FlashLoanState> = Item::new("flash_loan");
+
+#[cw_serde]
+pub struct WithdrawalState {
+    /// Pool whose LP tokens were sent to the vault
+    pub pool_address: String,
+    /// LP tokens sent, burned by the pool before the reply paying them out
+    pub liquidity: Uint128,
+    pub params: WithdrawLiquidityParams,
+}
+
+// WITHDRAWAL holds the LP tokens sent to the vault between their burn by the pool and the reply paying them out.
+pub const WITHDRAWAL: Item<WithdrawalState> = Item::new("withdrawal");